@@ -0,0 +1,70 @@
+//! Achievements
+//! Milestones reached during a run, announced in the message log
+
+use super::{GameEvent, GameLog, RunStats};
+
+/// Steps the player has to walk for the Wanderer achievement
+const WANDERER_STEPS: i32 = 500;
+
+/// Blobs that have to be destroyed for the Exterminator achievement
+const EXTERMINATOR_KILLS: i32 = 10;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Achievement {
+    FirstBlood,
+    Exterminator,
+    CrystalHunter,
+    Wanderer,
+}
+
+impl Achievement {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "First Blood",
+            Achievement::Exterminator => "Exterminator",
+            Achievement::CrystalHunter => "Crystal Hunter",
+            Achievement::Wanderer => "Wanderer",
+        }
+    }
+}
+
+/// Achievements unlocked in the current run
+#[derive(Default)]
+pub struct Achievements {
+    pub unlocked: Vec<Achievement>,
+    steps: i32,
+}
+
+impl Achievements {
+    fn unlock(&mut self, achievement: Achievement, log: &mut GameLog) {
+        if !self.unlocked.contains(&achievement) {
+            self.unlocked.push(achievement);
+            log.push(format!("Achievement unlocked: {}!", achievement.label()));
+        }
+    }
+
+    /// Checks the event against every milestone, the stats have
+    /// already been updated for the event
+    pub fn notify(&mut self, event: &GameEvent, stats: &RunStats, log: &mut GameLog) {
+        match event {
+            GameEvent::Died { .. } => {
+                self.unlock(Achievement::FirstBlood, log);
+                if stats.blobs_killed >= EXTERMINATOR_KILLS {
+                    self.unlock(Achievement::Exterminator, log);
+                }
+            }
+            GameEvent::ItemPickedUp {
+                crystal: true,
+                by_player: true,
+                ..
+            } => self.unlock(Achievement::CrystalHunter, log),
+            GameEvent::PlayerMoved { .. } => {
+                self.steps += 1;
+                if self.steps >= WANDERER_STEPS {
+                    self.unlock(Achievement::Wanderer, log);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,6 +1,9 @@
-///! Blob Module
-///! Contains the AI function to move the blob toward the player 
-use super::{Blob, Map, Position, Viewshed, WantsToMelee};
+//! Blob Module
+//! Contains the AI function to move the blob toward the player
+use super::{
+    realtime, swarm::doorway, Blob, BlobPaths, CombatStats, Cooldown, FlowMap, GameMode, Map,
+    MyTurn, Position, Senses, Swarm, Viewshed, WantsToMelee,
+};
 use rltk::Point;
 use specs::prelude::*;
 
@@ -13,34 +16,79 @@ impl<'a> System<'a> for BlobAi {
         WriteExpect<'a, Map>,
         ReadExpect<'a, Point>,
         ReadExpect<'a, Entity>,
+        ReadExpect<'a, GameMode>,
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Blob>,
+        ReadStorage<'a, MyTurn>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToMelee>,
+        WriteStorage<'a, Cooldown>,
+        ReadExpect<'a, FlowMap>,
+        ReadStorage<'a, CombatStats>,
+        WriteExpect<'a, Swarm>,
+        ReadExpect<'a, Senses>,
     );
-    /// Scans through the entities looking for blobs
+    /// Scans through the entities looking for blobs with a turn
     /// Blobs in attacking range will hit the player
-    /// Blobs in visual range will move toward the player
+    /// Blobs in visual range will move toward the player along the flow map,
+    /// close up they head for their own tile around the player from the swarm
+    /// Bosses hang back and only one blob goes through a door at a time
+    /// Blobs that can't see the player follow any noise or scent they pick up
+    /// Hurt blobs with more than one hit point run away from the player instead
+    /// In real-time mode blobs cool down after attacking
     fn run(&mut self, data: Self::SystemData) {
         let (
             mut map,
             player_pos,
             player_entity,
+            mode,
             entities,
             mut viewshed,
             blob,
+            turns,
             mut position,
             mut wants_to_melee,
+            mut cooldowns,
+            flow,
+            stats,
+            mut swarm,
+            senses,
         ) = data;
 
-        for (entity, viewshed, _blob, mut pos) in
-            (&entities, &mut viewshed, &blob, &mut position).join()
+        for (entity, viewshed, _blob, _turn, pos) in
+            (&entities, &mut viewshed, &blob, &turns, &mut position).join()
         {
-            let distance =
-                rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-            // Attack the player
-            if distance < 1.5 {
+            // Blobs still recovering from their last action wait
+            if cooldowns.get(entity).is_some() {
+                continue;
+            }
+
+            // Dead blobs are removed before they act again, tougher blobs
+            // that have been hurt run away, one hit blobs always fight
+            let (hp, max_hp) = stats
+                .get(entity)
+                .map_or((0, 0), |stats| (stats.hp, stats.max_hp));
+            if hp < 1 {
+                continue;
+            }
+            let fleeing = hp < max_hp && max_hp > 1;
+            let idx = map.index(pos.x, pos.y);
+            let flee_step = if fleeing && viewshed.visible_tiles.contains(&*player_pos) {
+                flow.step(&map, idx, true)
+            } else {
+                None
+            };
+
+            // Run away while there is somewhere to go
+            if let Some(next) = flee_step {
+                pos.x = next as i32 % map.width;
+                pos.y = next as i32 / map.width;
+                map.move_entity(entity, next, true);
+            // Attack the player, unless they are in a safe room
+            } else if map.can_melee((pos.x, pos.y), (player_pos.x, player_pos.y))
+                && !map.safe[map.index(player_pos.x, player_pos.y)]
+            {
                 wants_to_melee
                     .insert(
                         entity,
@@ -49,22 +97,56 @@ impl<'a> System<'a> for BlobAi {
                         },
                     )
                     .expect("Unable to insert attack");
+                if *mode == GameMode::RealTime {
+                    cooldowns
+                        .insert(
+                            entity,
+                            Cooldown {
+                                ms: realtime::ACTION_COOLDOWN_MS,
+                            },
+                        )
+                        .expect("Unable to insert cooldown");
+                }
             // Move toward the player
             } else if viewshed.visible_tiles.contains(&*player_pos) {
-                let path = rltk::a_star_search(
-                    map.index(pos.x, pos.y),
-                    map.index(player_pos.x, player_pos.y),
-                    &*map,
-                );
-                // If move is succesful set the map tile to occupied to prevent overlap
-                if path.success && path.steps.len() > 1 {
-                    let mut idx = map.index(pos.x, pos.y);
-                    map.occupied[idx] = false;
-                    pos.x = path.steps[1] as i32 % map.width;
-                    pos.y = path.steps[1] as i32 / map.width;
-                    idx = map.index(pos.x, pos.y);
-                    map.occupied[idx] = true;
+                if swarm.hang_back.contains(&entity) {
+                    continue;
+                }
+                // Close up each blob heads for its own tile so they surround the player
+                // A target another blob has just stepped onto can't be reached, so
+                // follow the flow map rather than searching the whole map for a path
+                let next = match swarm.targets.get(&entity) {
+                    Some(target) if !map.is_blocked(*target) => {
+                        let path = rltk::a_star_search(idx, *target, &BlobPaths { map: &map });
+                        if path.success && path.steps.len() > 1 {
+                            Some(path.steps[1])
+                        } else {
+                            flow.step(&map, idx, false)
+                        }
+                    }
+                    _ => flow.step(&map, idx, false),
+                };
+                let next = match next {
+                    Some(next) if !map.is_blocked(next) => next,
+                    _ => continue,
+                };
+                // Wait outside doors another blob is going through
+                if let Some(door) = doorway(&map, next) {
+                    if !swarm.claim_door(door, entity) {
+                        continue;
+                    }
+                } else {
+                    swarm.leave_door(entity);
                 }
+                // Move on the map straight away so blobs acting after this one can't overlap it
+                pos.x = next as i32 % map.width;
+                pos.y = next as i32 / map.width;
+                map.move_entity(entity, next, true);
+            // Investigate noises and follow the player's scent
+            } else if let Some(next) = senses.track(&map, idx) {
+                pos.x = next as i32 % map.width;
+                pos.y = next as i32 / map.width;
+                map.move_entity(entity, next, true);
             }
         }
     }
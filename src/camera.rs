@@ -0,0 +1,58 @@
+//! Camera
+//! Keeps the player in the middle of the screen and converts
+//! between map and screen coordinates for maps bigger than the screen
+
+use super::Map;
+use rltk::Point;
+use specs::prelude::*;
+
+/// Size of the map area of the screen, the HUD sits underneath
+pub const VIEW_WIDTH: i32 = 80;
+pub const VIEW_HEIGHT: i32 = 43;
+
+/// Map coordinates of the top left of the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub min_x: i32,
+    pub min_y: i32,
+}
+
+impl Camera {
+    /// Centers on the player without scrolling past the edges of the map
+    pub fn new(ecs: &World) -> Camera {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        Camera {
+            min_x: axis_start(player_pos.x, VIEW_WIDTH, map.width),
+            min_y: axis_start(player_pos.y, VIEW_HEIGHT, map.height),
+        }
+    }
+
+    /// Screen cell for a map tile, None when it is out of view
+    pub fn to_screen(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let screen_x = x - self.min_x;
+        let screen_y = y - self.min_y;
+        if !Camera::in_view(screen_x, screen_y) {
+            return None;
+        }
+        Some((screen_x, screen_y))
+    }
+
+    /// Map tile under a screen cell
+    pub fn to_world(&self, screen_x: i32, screen_y: i32) -> (i32, i32) {
+        (screen_x + self.min_x, screen_y + self.min_y)
+    }
+
+    /// True when the screen cell is part of the map view rather than the HUD
+    pub fn in_view(screen_x: i32, screen_y: i32) -> bool {
+        (0..VIEW_WIDTH).contains(&screen_x) && (0..VIEW_HEIGHT).contains(&screen_y)
+    }
+}
+
+/// First map tile shown along one axis
+fn axis_start(center: i32, view: i32, size: i32) -> i32 {
+    if size <= view {
+        return 0;
+    }
+    (center - view / 2).clamp(0, size - view)
+}
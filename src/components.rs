@@ -52,7 +52,7 @@ pub struct CombatStats {
     pub hp: i32,
 }
 
-/// Sets the target to attack when the melee system runs 
+/// Sets the target to attack when the melee system runs
 #[derive(Component, Debug, Clone)]
 pub struct WantsToMelee {
     pub target: Entity,
@@ -101,3 +101,59 @@ pub struct WantsToPickupItem {
     pub collected_by: Entity,
     pub item: Entity,
 }
+
+/// Energy gained every tick, the entity acts once its energy
+/// reaches the action cost in the initiative system
+#[derive(Component, Debug)]
+pub struct Initiative {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+/// Marks the entities that are allowed to act this tick
+#[derive(Component, Debug)]
+pub struct MyTurn {}
+
+/// Temporarily hastes an entity, added to its speed until the turns run out
+#[derive(Component, Debug, Clone)]
+pub struct SpeedEffect {
+    pub amount: i32,
+    pub turns: i32,
+}
+
+/// Time left in milliseconds before the entity can act again in real-time mode
+#[derive(Component, Debug)]
+pub struct Cooldown {
+    pub ms: f32,
+}
+
+/// Path the player is walking along after clicking a tile
+/// Stops when the player is hurt or a new blob comes into view
+#[derive(Component, Debug, Clone)]
+pub struct AutoTravel {
+    pub path: Vec<usize>,
+    pub hp: i32,
+    pub seen: Vec<Entity>,
+}
+
+/// Player keeps walking toward the nearest unexplored tile
+#[derive(Component, Debug)]
+pub struct AutoExplore {}
+
+/// Short lived effect drawn over the map, removed once its time runs out
+#[derive(Component, Debug)]
+pub struct ParticleLifetime {
+    pub lifetime_ms: f32,
+}
+
+/// Slowly heals the entity, counting the turns since it last healed
+#[derive(Component, Debug)]
+pub struct Regeneration {
+    pub turns: i32,
+}
+
+/// Player keeps waiting until healed or something disturbs them
+#[derive(Component, Debug)]
+pub struct AutoRest {
+    pub hp: i32,
+}
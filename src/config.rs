@@ -0,0 +1,25 @@
+//! Locates the files used to store user configuration
+
+use std::env;
+use std::path::PathBuf;
+
+/// Folder holding the game's config files, created if it is missing
+/// Uses the platform config folder and falls back to the working directory
+pub fn config_dir() -> PathBuf {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    let dir = match base {
+        Some(base) => base.join("blobs"),
+        None => PathBuf::from("."),
+    };
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+/// Full path to a file in the config folder
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
@@ -2,7 +2,10 @@
 //! Scans through all pending damage and assess damage
 //! When entities have no health deletes them and drops items
 
-use super::{CombatStats, Heal, Item, Name, Position, Renderable, SufferDamage};
+use super::{
+    spawner, Blob, BlobKind, CombatStats, Difficulty, EventQueue, GameEvent, Heal, Item, Map, Name,
+    Position, Renderable, SpeedEffect, SufferDamage,
+};
 use rltk::RGB;
 use specs::prelude::*;
 
@@ -10,72 +13,63 @@ pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
     type SystemData = (
+        Entities<'a>,
         WriteStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, EventQueue>,
     );
 
     /// Assess all pending damage events and then clears them
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage) = data;
+        let (entities, mut stats, mut damage, player_entity, positions, mut events) = data;
 
-        for (mut stats, damage) in (&mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
+        for (entity, stats, damage, pos) in (&entities, &mut stats, &damage, &positions).join() {
+            let amount = damage.amount.iter().sum::<i32>();
+            stats.hp -= amount;
+            events.publish(GameEvent::Damaged {
+                amount,
+                to_player: entity == *player_entity,
+                x: pos.x,
+                y: pos.y,
+            });
         }
 
         damage.clear();
     }
 }
 
-/// When blobs die boss blobs will drop a colored crystal
-/// grey blobs will drop a heal
+/// When blobs die boss blobs will drop a colored crystal that hastes
+/// whoever picks it up
+/// any other blobs will drop a heal
 pub fn drop_item(ecs: &mut World, x: i32, y: i32, i: String) -> Entity {
-    // Pick the color
-    let color: RGB;
-    if i == "RED" {
-        color = RGB::named(rltk::RED)
-    } else if i == "BLUE" {
-        color = RGB::named(rltk::BLUE)
-    } else if i == "PURPLE" {
-        color = RGB::named(rltk::PURPLE)
-    } else if i == "YELLOW" {
-        color = RGB::named(rltk::YELLOW)
-    } else {
-        color = RGB::named(rltk::GREY)
-    }
-
-    // Drop a heal
-    if i == "GREY" {
-        ecs.create_entity()
-            .with(Position { x, y })
-            .with(Renderable {
-                glyph: rltk::to_cp437('+'),
-                fg: RGB::named(rltk::RED),
-                bg: RGB::named(rltk::BLACK),
-            })
-            .with(Name {
-                name: "Health".to_string(),
-            })
-            .with(Item {})
-            .with(Heal { heal_amount: 4 })
-            .build()
     // Drop a colored crystal
-    } else {
+    if let Some(boss) = BlobKind::from_name(&i).filter(|kind| kind.is_boss()) {
+        let heal_amount = ecs.fetch::<Difficulty>().preset().heal_amount;
         ecs.create_entity()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: rltk::to_cp437('♦'),
-                fg: color,
+                fg: boss.color(),
                 bg: RGB::named(rltk::BLACK),
             })
             .with(Name { name: i })
             .with(Item {})
-            .with(Heal { heal_amount: 4 })
+            .with(Heal { heal_amount })
+            .with(SpeedEffect {
+                amount: 10,
+                turns: 10,
+            })
             .build()
+    // Drop a heal
+    } else {
+        spawner::health(ecs, x, y)
     }
 }
 
-/// Entities with no health are assessed for their drop
-/// then removed from the world
+/// Blobs with no health are assessed for their drop
+/// then removed from the world, the player is left for the game over screen
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
     let mut drops: Vec<(String, i32, i32)> = Vec::new();
@@ -85,22 +79,41 @@ pub fn delete_the_dead(ecs: &mut World) {
         let entities = ecs.entities();
         let names = ecs.read_storage::<Name>();
         let pos = ecs.read_storage::<Position>();
-        for (entity, stats, name, pos) in (&entities, &combat_stats, &names, &pos).join() {
+        let blobs = ecs.read_storage::<Blob>();
+        let renderables = ecs.read_storage::<Renderable>();
+        for (entity, stats, name, pos, _blob, render) in
+            (&entities, &combat_stats, &names, &pos, &blobs, &renderables).join()
+        {
             if stats.hp < 1 {
                 dead.push(entity);
-                if name.name != "Player" {
-                    drops.push((name.name.clone(), pos.x, pos.y))
-                }
+                drops.push((name.name.clone(), pos.x, pos.y));
+                ecs.write_resource::<EventQueue>().publish(GameEvent::Died {
+                    name: name.name.clone(),
+                    color: render.fg,
+                    x: pos.x,
+                    y: pos.y,
+                });
             }
         }
     }
 
     for victim in dead {
+        ecs.write_resource::<Map>().remove_entity(victim);
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 
     for items in drops {
-        let drop = drop_item(ecs, items.1, items.2, items.0);
-        ecs.insert(drop);
+        let crystal = BlobKind::is_crystal_name(&items.0);
+        ecs.write_resource::<EventQueue>()
+            .publish(GameEvent::ItemDropped {
+                name: items.0.clone(),
+                crystal,
+                x: items.1,
+                y: items.2,
+            });
+        let item = drop_item(ecs, items.1, items.2, items.0);
+        let mut map = ecs.write_resource::<Map>();
+        let idx = map.index(items.1, items.2);
+        map.move_entity(item, idx, false);
     }
 }
@@ -0,0 +1,89 @@
+//! Game events
+//! Systems publish what happened to a queue instead of updating the
+//! log, score and effects themselves, the queue is drained once per turn
+//! and every subscriber is told about each event in order
+
+use super::{Achievements, GameLog, Map, ParticleBuilder, RunStats, Senses};
+use rltk::RGB;
+use specs::prelude::*;
+
+/// Something that happened this turn, entities may already be deleted
+/// when it is handled so events carry the details subscribers need
+#[derive(Clone, Debug)]
+pub enum GameEvent {
+    Attacked {
+        attacker: String,
+        target: String,
+        amount: i32,
+        x: i32,
+        y: i32,
+    },
+    Damaged {
+        amount: i32,
+        to_player: bool,
+        x: i32,
+        y: i32,
+    },
+    Died {
+        name: String,
+        color: RGB,
+        x: i32,
+        y: i32,
+    },
+    ItemDropped {
+        name: String,
+        crystal: bool,
+        x: i32,
+        y: i32,
+    },
+    ItemPickedUp {
+        name: String,
+        crystal: bool,
+        by_player: bool,
+        color: RGB,
+        x: i32,
+        y: i32,
+    },
+    PlayerMoved {
+        x: i32,
+        y: i32,
+    },
+}
+
+/// Events published since the last time the queue was drained
+#[derive(Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+}
+
+/// Hands every queued event to the log, score tracker, particles, achievements and senses
+pub fn dispatch(ecs: &mut World) {
+    let events: Vec<GameEvent> = ecs
+        .write_resource::<EventQueue>()
+        .events
+        .drain(..)
+        .collect();
+    if events.is_empty() {
+        return;
+    }
+
+    let map = ecs.fetch::<Map>();
+    let mut log = ecs.write_resource::<GameLog>();
+    let mut stats = ecs.write_resource::<RunStats>();
+    let mut particles = ecs.write_resource::<ParticleBuilder>();
+    let mut achievements = ecs.write_resource::<Achievements>();
+    let mut senses = ecs.write_resource::<Senses>();
+    for event in events.iter() {
+        log.notify(event);
+        stats.notify(event);
+        particles.notify(event, &map);
+        achievements.notify(event, &stats, &mut log);
+        senses.notify(event, &map);
+    }
+}
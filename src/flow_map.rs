@@ -0,0 +1,68 @@
+//! Flow map
+//! One Dijkstra map of walking distance to the player shared by every
+//! blob, blobs step downhill to chase the player and uphill to flee
+
+use super::{BlobTerrain, Map};
+use rltk::{DijkstraMap, Point};
+use specs::prelude::*;
+
+/// Furthest distance from the player the map is filled to
+const MAX_DEPTH: f32 = 1000.0;
+
+/// Only walls and the safe rooms block the flow map so it stays valid while
+/// blobs move, it is rebuilt when the player moves
+/// Routes never pass through a room, so blobs don't stop in its doorway
+#[derive(Default)]
+pub struct FlowMap {
+    dijkstra: Option<DijkstraMap>,
+    origin: Option<usize>,
+}
+
+impl FlowMap {
+    /// Tile one step closer to the player, or further away when fleeing
+    /// Only free tiles outside the safe rooms are picked so blobs don't stack up
+    pub fn step(&self, map: &Map, from: usize, flee: bool) -> Option<usize> {
+        let dijkstra = self.dijkstra.as_ref()?;
+        // Fleeing follows the same map turned upside down
+        let value = |idx: usize| {
+            if flee {
+                -dijkstra.map[idx]
+            } else {
+                dijkstra.map[idx]
+            }
+        };
+        map.blob_exits(from)
+            .iter()
+            .map(|(idx, _)| *idx)
+            .filter(|idx| dijkstra.map[*idx] < f32::MAX && value(*idx) < value(from))
+            .min_by(|a, b| value(*a).total_cmp(&value(*b)))
+    }
+}
+
+pub struct FlowMapSystem {}
+
+impl<'a> System<'a> for FlowMapSystem {
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        WriteExpect<'a, FlowMap>,
+    );
+
+    /// Rebuilds the map from the player's tile once they have moved
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, player_pos, mut flow) = data;
+        let player_idx = map.index(player_pos.x, player_pos.y);
+        if flow.origin == Some(player_idx) {
+            return;
+        }
+        let terrain = BlobTerrain { map: &map };
+        flow.dijkstra = Some(DijkstraMap::new(
+            map.width,
+            map.height,
+            &[player_idx],
+            &terrain,
+            MAX_DEPTH,
+        ));
+        flow.origin = Some(player_idx);
+    }
+}
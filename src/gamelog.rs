@@ -0,0 +1,52 @@
+//! Message log
+//! Holds the messages shown in the HUD
+
+use super::GameEvent;
+
+/// Oldest messages are dropped once the log is this long
+const LOG_LENGTH: usize = 50;
+
+#[derive(Default)]
+pub struct GameLog {
+    pub entries: Vec<String>,
+}
+
+impl GameLog {
+    pub fn push(&mut self, message: String) {
+        self.entries.push(message);
+        if self.entries.len() > LOG_LENGTH {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Describes the events the player should hear about
+    pub fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Attacked {
+                attacker,
+                target,
+                amount,
+                ..
+            } => self.push(format!("{} hits {}, for {} hp.", attacker, target, amount)),
+            GameEvent::Died { name, .. } => self.push(format!("{} is destroyed.", name)),
+            GameEvent::ItemDropped {
+                name,
+                crystal: true,
+                ..
+            } => self.push(format!("{} dropped its crystal.", name)),
+            GameEvent::ItemPickedUp {
+                name,
+                crystal,
+                by_player: true,
+                ..
+            } => {
+                if *crystal {
+                    self.push(format!("You collect the {} crystal.", name));
+                } else {
+                    self.push(format!("You pick up the {}.", name.to_lowercase()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,9 +1,13 @@
 //! Renders the players information and menu
-use super::{CombatStats, Inventory, Name, Player};
-use rltk::{Rltk, VirtualKeyCode, RGB};
+use super::{
+    camera::{VIEW_HEIGHT, VIEW_WIDTH},
+    Action, BlobKind, Camera, CombatStats, Cooldown, Difficulty, GameLog, GameMode, HighScoreTable,
+    Inventory, Item, KeyBindings, Map, Name, Player, Position, Renderable, RunStats, Settings,
+    SpeedEffect, Themes, TileType,
+};
+use rltk::{Point, Rltk, RGB};
 use specs::prelude::*;
 
-
 /// Draws the player health as a number and a bar
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     // Main HUD box
@@ -42,8 +46,67 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         );
     }
 
+    // Show when the world is running in real-time
+    if *ecs.fetch::<GameMode>() == GameMode::RealTime {
+        ctx.print_color(
+            66,
+            44,
+            RGB::named(rltk::ORANGE),
+            RGB::named(rltk::BLACK),
+            "REAL-TIME",
+        );
+    }
+
+    // Draw the difficulty of the run
+    ctx.print_color(
+        6,
+        48,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("Difficulty: {:?}", *ecs.fetch::<Difficulty>()),
+    );
+
+    // Draw the most recent messages
+    let log = ecs.fetch::<GameLog>();
+    let log_size = ecs.fetch::<Settings>().log_size;
+    for (i, message) in log.entries.iter().rev().take(log_size).enumerate() {
+        ctx.print_color(
+            40,
+            45 + i as i32,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            message,
+        );
+    }
+
     // Get the items in the players inventory
     let player_entity = ecs.fetch::<Entity>();
+
+    // Draw haste status
+    let speed_effects = ecs.read_storage::<SpeedEffect>();
+    if let Some(effect) = speed_effects.get(*player_entity) {
+        ctx.print_color(
+            6,
+            44,
+            RGB::named(rltk::CYAN),
+            RGB::named(rltk::BLACK),
+            format!("Hasted ({})", effect.turns),
+        );
+    }
+
+    // Show when the player is in a safe room
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    if map.safe[map.index(player_pos.x, player_pos.y)] {
+        ctx.print_color(
+            24,
+            44,
+            RGB::named(rltk::GREEN),
+            RGB::named(rltk::BLACK),
+            "SAFE ROOM",
+        );
+    }
+
     let inventory = ecs.read_storage::<Inventory>();
     let names = ecs.read_storage::<Name>();
     let mut x = 20;
@@ -57,104 +120,516 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         "Crystals: ".to_string(),
     );
 
-    // Draw the crystals the player has in their inventory
-    for (_pack, name) in (&inventory, &names)
+    // Draw the crystals the player has in their inventory, other items aren't crystals
+    let crystals = (&inventory, &names)
         .join()
         .filter(|item| item.0.owner == *player_entity)
-    {
-        let color: RGB;
-        // Get the correct color to draw
-        if name.name == "RED" {
-            color = RGB::named(rltk::RED)
-        } else if name.name == "BLUE" {
-            color = RGB::named(rltk::BLUE)
-        } else if name.name == "PURPLE" {
-            color = RGB::named(rltk::PURPLE)
-        } else {
-            color = RGB::named(rltk::YELLOW)
-        }
+        .filter_map(|(_pack, name)| BlobKind::from_name(&name.name))
+        .filter(|kind| kind.is_boss());
+    for kind in crystals {
         // Draw the crystal and increment x to space the next crystal
-        ctx.print_color(x, 46, color, RGB::named(rltk::BLACK), "♦".to_string());
+        ctx.print_color(
+            x,
+            46,
+            kind.color(),
+            RGB::named(rltk::BLACK),
+            "♦".to_string(),
+        );
         x += 4;
     }
 }
 
+/// Lists the name, health and status of everything on the tile under the mouse
+pub fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let names = ecs.read_storage::<Name>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let speed_effects = ecs.read_storage::<SpeedEffect>();
+    let cooldowns = ecs.read_storage::<Cooldown>();
+
+    // Only tiles on the map have tooltips
+    let (mouse_x, mouse_y) = ctx.mouse_pos();
+    if !Camera::in_view(mouse_x, mouse_y) {
+        return;
+    }
+    let (tile_x, tile_y) = Camera::new(ecs).to_world(mouse_x, mouse_y);
+    if tile_x < 0 || tile_x >= map.width || tile_y < 0 || tile_y >= map.height {
+        return;
+    }
+
+    // Build a line for each entity on the tile
+    let mut lines: Vec<String> = Vec::new();
+    for entity in map.tile_content[map.index(tile_x, tile_y)].iter() {
+        if let Some(name) = names.get(*entity) {
+            let mut line = name.name.clone();
+            if let Some(stats) = combat_stats.get(*entity) {
+                line += &format!(" HP: {}/{}", stats.hp, stats.max_hp);
+                // Crystals on the floor hold the haste they give, only fighters are hasted
+                if speed_effects.get(*entity).is_some() {
+                    line += " Hasted";
+                }
+            }
+            if cooldowns.get(*entity).is_some() {
+                line += " Recovering";
+            }
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        return;
+    }
+
+    // Size the box to the longest line and keep it on the screen
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as i32
+        + 3;
+    let height = lines.len() as i32 + 1;
+    let x = if mouse_x + width + 1 < VIEW_WIDTH {
+        mouse_x + 1
+    } else {
+        mouse_x - width - 1
+    };
+    let y = mouse_y.min(VIEW_HEIGHT - height - 1);
+
+    ctx.draw_box(
+        x,
+        y,
+        width,
+        height,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            x + 2,
+            y + 1 + i as i32,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            line,
+        );
+    }
+
+    // Highlight the hovered tile
+    ctx.set_bg(mouse_x, mouse_y, RGB::named(rltk::DARK_GREY));
+}
+
+/// Whether the minimap overlay is drawn
+#[derive(Default)]
+pub struct Minimap {
+    pub visible: bool,
+}
+
+/// Map tiles covered by each minimap cell
+const MINIMAP_SCALE: i32 = 3;
+
+/// Draws a scaled down copy of the whole level in the top right corner
+/// with the rooms, the player and any crystals the player has seen
+pub fn draw_minimap(ecs: &World, ctx: &mut Rltk) {
+    if !ecs.fetch::<Minimap>().visible {
+        return;
+    }
+    let map = ecs.fetch::<Map>();
+    let player_pos = ecs.fetch::<Point>();
+    let wall = ecs
+        .fetch::<Themes>()
+        .get(&ecs.fetch::<Settings>().theme)
+        .style(TileType::Wall)
+        .fg;
+    let names = ecs.read_storage::<Name>();
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+
+    let width = (map.width + MINIMAP_SCALE - 1) / MINIMAP_SCALE;
+    let height = (map.height + MINIMAP_SCALE - 1) / MINIMAP_SCALE;
+    let left = VIEW_WIDTH - width - 2;
+    let top = 1;
+    ctx.draw_box(
+        left - 1,
+        top - 1,
+        width + 1,
+        height + 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+
+    // Revealed areas are shaded, unexplored areas stay black
+    for cell_y in 0..height {
+        for cell_x in 0..width {
+            let revealed = (0..MINIMAP_SCALE).any(|dy| {
+                (0..MINIMAP_SCALE).any(|dx| {
+                    let x = cell_x * MINIMAP_SCALE + dx;
+                    let y = cell_y * MINIMAP_SCALE + dy;
+                    x < map.width && y < map.height && map.revealed_tiles[map.index(x, y)]
+                })
+            });
+            if revealed {
+                ctx.set_bg(left + cell_x, top + cell_y, RGB::named(rltk::DARK_GREY));
+            }
+        }
+    }
+
+    // Room outlines
+    for room in map.rooms.iter() {
+        let x1 = (room.x1 + 1) / MINIMAP_SCALE;
+        let y1 = (room.y1 + 1) / MINIMAP_SCALE;
+        let x2 = room.x2 / MINIMAP_SCALE;
+        let y2 = room.y2 / MINIMAP_SCALE;
+        ctx.draw_hollow_box(
+            left + x1,
+            top + y1,
+            x2 - x1,
+            y2 - y1,
+            wall,
+            RGB::named(rltk::BLACK),
+        );
+    }
+
+    // Crystals on tiles the player has seen
+    for (name, _item, pos, render) in (&names, &items, &positions, &renderables).join() {
+        let crystal = BlobKind::is_crystal_name(&name.name);
+        if crystal && map.revealed_tiles[map.index(pos.x, pos.y)] {
+            ctx.set(
+                left + pos.x / MINIMAP_SCALE,
+                top + pos.y / MINIMAP_SCALE,
+                render.fg,
+                RGB::named(rltk::BLACK),
+                render.glyph,
+            );
+        }
+    }
+
+    ctx.set(
+        left + player_pos.x / MINIMAP_SCALE,
+        top + player_pos.y / MINIMAP_SCALE,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437('@'),
+    );
+}
+
 /// Controls menu interactions
 #[derive(PartialEq, Copy, Clone)]
 pub enum MenuResult {
     Cancel,
     NoResponse,
+    Highlight(usize),
+    Selected(usize),
 }
 
-/// Brings up the menu for the player to choose from
-/// Selection is controlled with specific key selection
-pub fn show_menu(ctx: &mut Rltk) -> MenuResult {
-    // Number of menu options (row spacing inside box)
-    let count = 1;
-    let y = (25 - (count / 2)) as i32;
+/// Draws any list of options in a box in the center of the screen
+/// The selected row is highlighted and moved with the menu keys
+pub fn draw_menu(
+    ctx: &mut Rltk,
+    bindings: &KeyBindings,
+    title: &str,
+    options: &[String],
+    selected: usize,
+) -> MenuResult {
+    let cancel = format!(
+        "{} to cancel",
+        bindings.key_for(Action::CloseMenu).to_uppercase()
+    );
+
+    // Size the box around the longest row
+    let count = options.len() as i32;
+    let longest = options
+        .iter()
+        .map(|option| option.chars().count())
+        .chain(vec![title.chars().count(), cancel.chars().count()])
+        .max()
+        .unwrap_or(0) as i32;
+    let (screen_width, screen_height) = ctx.get_char_size();
+    let width = longest + 5;
+    let x = (screen_width as i32 - width) / 2;
+    let y = (screen_height as i32 - count) / 2;
 
-    // Draw menu box in center of screen with
+    // Draw menu box with the title and footer on the border
     ctx.draw_box(
-        15,
+        x,
         y - 2,
-        31,
-        (count + 3) as i32,
+        width,
+        count + 3,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
     );
     ctx.print_color(
-        18,
+        x + 3,
         y - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Menu",
+        title,
     );
     ctx.print_color(
-        18,
-        y + count as i32 + 1,
+        x + 3,
+        y + count + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "ESCAPE to cancel",
+        &cancel,
     );
 
-    // Draw first row - Quit
-    ctx.set(
-        17,
+    // Draw a row for each option
+    for (i, option) in options.iter().enumerate() {
+        let (fg, bg) = if i == selected {
+            (RGB::named(rltk::BLACK), RGB::named(rltk::YELLOW))
+        } else {
+            (RGB::named(rltk::WHITE), RGB::named(rltk::BLACK))
+        };
+        ctx.print_color(x + 2, y + i as i32, fg, bg, option);
+    }
+
+    // Menu select options by the bound keys
+    if options.is_empty() {
+        return MenuResult::Cancel;
+    }
+    match ctx.key.and_then(|key| bindings.menu_action(key)) {
+        Some(Action::MenuUp) => {
+            MenuResult::Highlight((selected + options.len() - 1) % options.len())
+        }
+        Some(Action::MenuDown) => MenuResult::Highlight((selected + 1) % options.len()),
+        Some(Action::MenuSelect) => MenuResult::Selected(selected),
+        Some(Action::CloseMenu) => MenuResult::Cancel,
+        _ => MenuResult::NoResponse,
+    }
+}
+
+/// Rows of the title screen
+#[derive(PartialEq, Copy, Clone)]
+pub enum MainMenuEntry {
+    NewGame,
+    Continue,
+    Options,
+    HighScores,
+    Quit,
+}
+
+/// Continue is only offered once a game has been started
+pub fn main_menu_entries(can_continue: bool) -> Vec<MainMenuEntry> {
+    let mut entries = vec![MainMenuEntry::NewGame];
+    if can_continue {
+        entries.push(MainMenuEntry::Continue);
+    }
+    entries.push(MainMenuEntry::Options);
+    entries.push(MainMenuEntry::HighScores);
+    entries.push(MainMenuEntry::Quit);
+    entries
+}
+
+impl MainMenuEntry {
+    pub fn label(&self) -> String {
+        match self {
+            MainMenuEntry::NewGame => "New Game",
+            MainMenuEntry::Continue => "Continue",
+            MainMenuEntry::Options => "Options",
+            MainMenuEntry::HighScores => "High Scores",
+            MainMenuEntry::Quit => "Quit",
+        }
+        .to_string()
+    }
+}
+
+/// Rows of the menu opened during play
+#[derive(PartialEq, Copy, Clone)]
+pub enum GameMenuEntry {
+    Resume,
+    Options,
+    MainMenu,
+    Quit,
+}
+
+impl GameMenuEntry {
+    pub const ALL: [GameMenuEntry; 4] = [
+        GameMenuEntry::Resume,
+        GameMenuEntry::Options,
+        GameMenuEntry::MainMenu,
+        GameMenuEntry::Quit,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            GameMenuEntry::Resume => "Resume",
+            GameMenuEntry::Options => "Options",
+            GameMenuEntry::MainMenu => "Main Menu",
+            GameMenuEntry::Quit => "Quit",
+        }
+        .to_string()
+    }
+}
+
+/// Rows of the options screen
+#[derive(PartialEq, Copy, Clone)]
+pub enum OptionsEntry {
+    RealTime,
+    Scanlines,
+    Difficulty,
+    Theme,
+    KeyRepeat,
+    LogSize,
+    Back,
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+impl OptionsEntry {
+    pub const ALL: [OptionsEntry; 7] = [
+        OptionsEntry::RealTime,
+        OptionsEntry::Scanlines,
+        OptionsEntry::Difficulty,
+        OptionsEntry::Theme,
+        OptionsEntry::KeyRepeat,
+        OptionsEntry::LogSize,
+        OptionsEntry::Back,
+    ];
+
+    pub fn label(&self, settings: &Settings) -> String {
+        match self {
+            OptionsEntry::RealTime => format!("Real-time: {}", on_off(settings.realtime)),
+            OptionsEntry::Scanlines => format!("Scanlines: {}", on_off(settings.scanlines)),
+            OptionsEntry::Difficulty => format!("Difficulty: {:?}", settings.difficulty),
+            OptionsEntry::Theme => format!("Theme: {}", settings.theme),
+            OptionsEntry::KeyRepeat => format!("Key repeat: {}", on_off(settings.key_repeat)),
+            OptionsEntry::LogSize => format!("Message log: {} lines", settings.log_size),
+            OptionsEntry::Back => "Back".to_string(),
+        }
+    }
+}
+
+/// Draws the high score table as rows starting at y
+fn draw_score_table(ctx: &mut Rltk, table: &HighScoreTable, y: i32) {
+    ctx.print_color(
+        12,
         y,
-        RGB::named(rltk::WHITE),
+        RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        rltk::to_cp437('('),
+        format!(
+            "{:<4}{:>7}  {:<12}{:<10}{:<22}{}",
+            "#", "Score", "Date", "Level", "Seed", "Result"
+        ),
     );
-    ctx.set(
+    if table.scores.is_empty() {
+        ctx.print(12, y + 2, "No runs recorded yet");
+    }
+    for (i, entry) in table.scores.iter().enumerate() {
+        let result = if entry.victory { "Won" } else { "Died" };
+        ctx.print(
+            12,
+            y + 2 + i as i32,
+            format!(
+                "{:<4}{:>7}  {:<12}{:<10}{:<22}{}",
+                i + 1,
+                entry.score,
+                entry.date,
+                format!("{:?}", entry.difficulty),
+                entry.seed,
+                result
+            ),
+        );
+    }
+}
+
+/// Returns Selected once the player presses a menu key to leave the screen
+fn wait_for_menu_key(ctx: &Rltk, bindings: &KeyBindings) -> MenuResult {
+    match ctx.key.and_then(|key| bindings.menu_action(key)) {
+        Some(Action::MenuSelect) | Some(Action::CloseMenu) => MenuResult::Selected(0),
+        _ => MenuResult::NoResponse,
+    }
+}
+
+/// Shows the high score table from the title screen
+pub fn show_high_scores(
+    ctx: &mut Rltk,
+    bindings: &KeyBindings,
+    table: &HighScoreTable,
+) -> MenuResult {
+    ctx.draw_box(
+        10,
+        8,
+        60,
         18,
-        y,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        13,
+        8,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        113_u16,
+        "High Scores",
     );
-    ctx.set(
-        19,
-        y,
+    draw_score_table(ctx, table, 10);
+    ctx.print_color(
+        13,
+        26,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{} to return",
+            bindings.key_for(Action::MenuSelect).to_uppercase()
+        ),
+    );
+    wait_for_menu_key(ctx, bindings)
+}
+
+/// Shows the summary of a finished run and the high score table
+pub fn show_game_over(
+    ctx: &mut Rltk,
+    bindings: &KeyBindings,
+    victory: bool,
+    stats: &RunStats,
+    table: &HighScoreTable,
+) -> MenuResult {
+    let (title, color) = if victory {
+        ("All the crystals are yours!", RGB::named(rltk::GREEN))
+    } else {
+        ("The blobs got you", RGB::named(rltk::RED))
+    };
+
+    ctx.draw_box(
+        10,
+        4,
+        60,
+        24,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
-        rltk::to_cp437(')'),
-    );
-    ctx.print(21, y, "Quit".to_string());
-
-    //y += 1;
-    // To add more options increase 'count' variable above and incriment y for every new row
-
-    // Menu select options by Key
-    match ctx.key {
-        None => MenuResult::NoResponse,
-        Some(key) => {
-            match key {
-                // Leave the Menu
-                VirtualKeyCode::Escape => MenuResult::Cancel,
-                // Quit the game
-                VirtualKeyCode::Q => std::process::exit(0),
-                _ => MenuResult::NoResponse,
-            }
-        }
+    );
+    ctx.print_color(13, 4, color, RGB::named(rltk::BLACK), title);
+
+    // Run summary
+    let lines = [
+        format!("Score: {}", stats.score(victory)),
+        format!("Difficulty: {:?}   Seed: {}", stats.difficulty, stats.seed),
+        format!(
+            "Blobs killed: {}   Crystals: {}",
+            stats.blobs_killed, stats.crystals_collected
+        ),
+        format!(
+            "Turns survived: {}   Damage taken: {}",
+            stats.turns, stats.damage_taken
+        ),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print(12, 6 + i as i32, line);
     }
+
+    draw_score_table(ctx, table, 11);
+    ctx.print_color(
+        13,
+        28,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{} to return to the title screen",
+            bindings.key_for(Action::MenuSelect).to_uppercase()
+        ),
+    );
+    wait_for_menu_key(ctx, bindings)
 }
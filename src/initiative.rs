@@ -0,0 +1,72 @@
+//! Initiative system
+//! Every tick each actor gains energy equal to its speed and
+//! takes a turn once it has enough energy to pay for an action
+
+use super::{Initiative, MyTurn, RunState, SpeedEffect};
+use specs::prelude::*;
+
+/// Energy spent by a single action
+pub const ACTION_COST: i32 = 100;
+
+/// Slowest an entity can be made by speed effects
+const MIN_SPEED: i32 = 5;
+
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Initiative>,
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, SpeedEffect>,
+        WriteExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+    );
+
+    /// Hands out energy and marks the entities that can act
+    /// Stops ticking when the player gets a turn
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut initiatives, mut turns, mut effects, mut runstate, player_entity) = data;
+
+        if *runstate != RunState::Ticking {
+            return;
+        }
+
+        // Turns only last for a single tick
+        turns.clear();
+
+        let mut expired: Vec<Entity> = Vec::new();
+        for (entity, initiative) in (&entities, &mut initiatives).join() {
+            let mut speed = initiative.speed;
+            if let Some(effect) = effects.get(entity) {
+                speed += effect.amount;
+            }
+            initiative.energy += speed.max(MIN_SPEED);
+
+            if initiative.energy < ACTION_COST {
+                continue;
+            }
+            initiative.energy -= ACTION_COST;
+            turns
+                .insert(entity, MyTurn {})
+                .expect("Unable to insert turn");
+
+            // Speed effects wear off as the entity takes turns
+            if let Some(effect) = effects.get_mut(entity) {
+                effect.turns -= 1;
+                if effect.turns < 1 {
+                    expired.push(entity);
+                }
+            }
+
+            if entity == *player_entity {
+                *runstate = RunState::AwaitingInput;
+            }
+        }
+
+        for entity in expired {
+            effects.remove(entity);
+        }
+    }
+}
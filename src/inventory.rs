@@ -1,9 +1,12 @@
 //! Inventory system
 //! Scans the vector of items pending pickup
 //! and assigns them on a first come first serve basis
-//! then removes them from the map
+//! then removes them from the map, healing items are used straight away
 
-use super::{Inventory, Position, WantsToPickupItem};
+use super::{
+    BlobKind, CombatStats, EventQueue, GameEvent, Heal, Inventory, Map, Name, Position, Renderable,
+    SpeedEffect, WantsToPickupItem,
+};
 use specs::prelude::*;
 
 pub struct ItemCollectionSystem {}
@@ -14,14 +17,69 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, WantsToPickupItem>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Inventory>,
+        WriteStorage<'a, SpeedEffect>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Renderable>,
+        WriteExpect<'a, EventQueue>,
+        WriteExpect<'a, Map>,
+        Entities<'a>,
+        ReadStorage<'a, Heal>,
+        WriteStorage<'a, CombatStats>,
     );
 
     /// Scan pending items remove their position and assign an owner
+    /// Picked up items are taken off the map index
+    /// Crystals haste whoever collects them
+    /// Healing items heal whoever collects them, crystals are kept and the rest used up
     fn run(&mut self, data: Self::SystemData) {
-        let (mut wants_pickup, mut positions, mut backpack) = data;
+        let (
+            mut wants_pickup,
+            mut positions,
+            mut backpack,
+            mut speed_effects,
+            names,
+            player_entity,
+            renderables,
+            mut events,
+            mut map,
+            entities,
+            heals,
+            mut stats,
+        ) = data;
 
         for pickup in wants_pickup.join() {
+            let crystal = names
+                .get(pickup.item)
+                .is_some_and(|name| BlobKind::is_crystal_name(&name.name));
+
+            if let (Some(name), Some(pos), Some(render)) = (
+                names.get(pickup.item),
+                positions.get(pickup.item),
+                renderables.get(pickup.item),
+            ) {
+                events.publish(GameEvent::ItemPickedUp {
+                    name: name.name.clone(),
+                    crystal,
+                    by_player: pickup.collected_by == *player_entity,
+                    color: render.fg,
+                    x: pos.x,
+                    y: pos.y,
+                });
+            }
             positions.remove(pickup.item);
+            map.remove_entity(pickup.item);
+
+            if let (Some(heal), Some(stats)) =
+                (heals.get(pickup.item), stats.get_mut(pickup.collected_by))
+            {
+                stats.hp = (stats.hp + heal.heal_amount).min(stats.max_hp);
+                if !crystal {
+                    entities.delete(pickup.item).expect("Unable to use up item");
+                    continue;
+                }
+            }
+
             backpack
                 .insert(
                     pickup.item,
@@ -30,8 +88,89 @@ impl<'a> System<'a> for ItemCollectionSystem {
                     },
                 )
                 .expect("Unable to insert backpack entry");
+
+            // Items with a speed effect pass it on to whoever picks them up
+            if let Some(effect) = speed_effects.remove(pickup.item) {
+                speed_effects
+                    .insert(pickup.collected_by, effect)
+                    .expect("Unable to insert speed effect");
+            }
         }
 
         wants_pickup.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spawner, Difficulty, State};
+    use rltk::RandomNumberGenerator;
+
+    /// A world with a hurt player standing on a map
+    fn world() -> (World, Entity) {
+        let mut ecs = State::new().ecs;
+        ecs.insert(Difficulty::Normal);
+        ecs.insert(Map::map_gen(&mut RandomNumberGenerator::seeded(1)));
+        let player = spawner::player(&mut ecs, 5, 5);
+        ecs.insert(player);
+        ecs.write_storage::<CombatStats>()
+            .get_mut(player)
+            .expect("Player has stats")
+            .hp = 3;
+        (ecs, player)
+    }
+
+    fn pick_up(ecs: &mut World, player: Entity, item: Entity) {
+        ecs.write_storage::<WantsToPickupItem>()
+            .insert(
+                item,
+                WantsToPickupItem {
+                    collected_by: player,
+                    item,
+                },
+            )
+            .expect("Unable to insert pickup");
+        let mut collection = ItemCollectionSystem {};
+        collection.run_now(ecs);
+        ecs.maintain();
+    }
+
+    fn hp(ecs: &World, player: Entity) -> i32 {
+        ecs.read_storage::<CombatStats>()
+            .get(player)
+            .map_or(0, |stats| stats.hp)
+    }
+
+    #[test]
+    fn health_heals_and_is_used_up() {
+        let (mut ecs, player) = world();
+        let heal_amount = Difficulty::Normal.preset().heal_amount;
+        let item = spawner::health(&mut ecs, 5, 5);
+        pick_up(&mut ecs, player, item);
+        assert_eq!(hp(&ecs, player), 3 + heal_amount);
+        assert!(!ecs.is_alive(item));
+    }
+
+    #[test]
+    fn healing_stops_at_max_hp() {
+        let (mut ecs, player) = world();
+        for _ in 0..5 {
+            let item = spawner::health(&mut ecs, 5, 5);
+            pick_up(&mut ecs, player, item);
+        }
+        assert_eq!(hp(&ecs, player), Difficulty::Normal.preset().player_hp);
+    }
+
+    #[test]
+    fn crystals_heal_and_are_kept() {
+        let (mut ecs, player) = world();
+        let crystal = crate::damage_system::drop_item(&mut ecs, 5, 5, "RED".to_string());
+        pick_up(&mut ecs, player, crystal);
+        assert!(hp(&ecs, player) > 3);
+        assert!(ecs.is_alive(crystal));
+        assert!(ecs.read_storage::<Inventory>().get(crystal).is_some());
+        assert!(ecs.read_storage::<SpeedEffect>().get(crystal).is_none());
+        assert!(ecs.read_storage::<SpeedEffect>().get(player).is_some());
+    }
+}
@@ -0,0 +1,324 @@
+//! Key bindings
+//! Maps keys to game actions, loaded from a config file so
+//! the controls can be remapped for other keyboard layouts
+
+use super::config;
+use rltk::{Rltk, VirtualKeyCode, INPUT};
+use std::fs;
+
+/// File in the config folder holding the bindings
+const BINDINGS_FILE: &str = "keys.cfg";
+
+/// Everything the player can do with a key
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    ShootNorth,
+    ShootSouth,
+    ShootWest,
+    ShootEast,
+    Pickup,
+    Explore,
+    Wait,
+    Rest,
+    ToggleMinimap,
+    OpenMenu,
+    CloseMenu,
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+}
+
+impl Action {
+    pub const ALL: [Action; 22] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+        Action::MoveNorthWest,
+        Action::MoveNorthEast,
+        Action::MoveSouthWest,
+        Action::MoveSouthEast,
+        Action::ShootNorth,
+        Action::ShootSouth,
+        Action::ShootWest,
+        Action::ShootEast,
+        Action::Pickup,
+        Action::Explore,
+        Action::Wait,
+        Action::Rest,
+        Action::ToggleMinimap,
+        Action::OpenMenu,
+        Action::CloseMenu,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuSelect,
+    ];
+
+    /// Menu actions are looked up separately so they can share keys with play
+    pub fn is_menu(&self) -> bool {
+        matches!(
+            self,
+            Action::CloseMenu | Action::MenuUp | Action::MenuDown | Action::MenuSelect
+        )
+    }
+
+    /// Keys used when there is no config file
+    fn default_keys(&self) -> Vec<VirtualKeyCode> {
+        use VirtualKeyCode::*;
+        match self {
+            Action::MoveNorth => vec![W, Numpad8],
+            Action::MoveSouth => vec![S, Numpad2],
+            Action::MoveWest => vec![A, Numpad4],
+            Action::MoveEast => vec![D, Numpad6],
+            Action::MoveNorthWest => vec![Q, Numpad7],
+            Action::MoveNorthEast => vec![E, Numpad9],
+            Action::MoveSouthWest => vec![Z, Numpad1],
+            Action::MoveSouthEast => vec![C, Numpad3],
+            Action::ShootNorth => vec![Up],
+            Action::ShootSouth => vec![Down],
+            Action::ShootWest => vec![Left],
+            Action::ShootEast => vec![Right],
+            Action::Pickup => vec![G],
+            Action::Explore => vec![X],
+            Action::Wait => vec![Period, Numpad5],
+            Action::Rest => vec![R],
+            Action::ToggleMinimap => vec![M],
+            Action::OpenMenu => vec![Escape],
+            Action::CloseMenu => vec![Escape],
+            Action::MenuUp => vec![Up, Numpad8],
+            Action::MenuDown => vec![Down, Numpad2],
+            Action::MenuSelect => vec![Return, Space],
+        }
+    }
+}
+
+/// Keys that can be named in the config file
+const KEYS: [VirtualKeyCode; 66] = {
+    use VirtualKeyCode::*;
+    [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Key0, Key1,
+        Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Up, Down, Left, Right, Escape, Space,
+        Return, Tab, Back, Comma, Period, Slash, Semicolon, Apostrophe, Minus, Equals, LBracket,
+        RBracket, Backslash, Grave,
+    ]
+};
+
+fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+    KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+fn parse_action(name: &str) -> Option<Action> {
+    Action::ALL
+        .iter()
+        .copied()
+        .find(|action| format!("{:?}", action) == name)
+}
+
+/// Keys bound to each action, in the order they were bound
+pub struct KeyBindings {
+    pub bindings: Vec<(VirtualKeyCode, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = Vec::new();
+        for action in Action::ALL.iter() {
+            for key in action.default_keys() {
+                bindings.push((key, *action));
+            }
+        }
+        KeyBindings { bindings }
+    }
+}
+
+impl KeyBindings {
+    /// Finds the action for a key, play and menu keys are looked up separately
+    fn lookup(&self, key: VirtualKeyCode, menu: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, action)| *bound == key && action.is_menu() == menu)
+            .map(|(_key, action)| *action)
+    }
+
+    /// Action bound to a key while playing
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.lookup(key, false)
+    }
+
+    /// Action bound to a key while the menu is open
+    pub fn menu_action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.lookup(key, true)
+    }
+
+    /// Names of every key bound to an action
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_key, bound)| *bound == action)
+            .map(|(key, _bound)| key_name(*key))
+            .collect()
+    }
+
+    /// First key bound to an action, used to label menus
+    pub fn key_for(&self, action: Action) -> String {
+        self.keys_for(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "?".to_string())
+    }
+
+    /// Reads `Action = Key, Key` lines, actions left out of the file keep their defaults
+    pub fn parse(text: &str) -> KeyBindings {
+        let mut bindings = KeyBindings::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let action = match parts.next().and_then(|name| parse_action(name.trim())) {
+                Some(action) => action,
+                None => continue,
+            };
+
+            // Replace the default keys for this action
+            bindings.bindings.retain(|(_key, bound)| *bound != action);
+            for name in parts.next().unwrap_or("").split(',') {
+                if let Some(key) = parse_key(name.trim()) {
+                    bindings.bindings.push((key, action));
+                }
+            }
+        }
+        bindings
+    }
+
+    /// Writes the bindings in the same format `parse` reads
+    pub fn to_config(&self) -> String {
+        let mut text = String::from("# Attack of the Blobs key bindings\n# Action = Key, Key\n");
+        for action in Action::ALL.iter() {
+            text += &format!("{:?} = {}\n", action, self.keys_for(*action).join(", "));
+        }
+        text
+    }
+
+    /// Loads the bindings from the config folder, writing the defaults if there are none
+    pub fn load() -> KeyBindings {
+        let path = config::config_file(BINDINGS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(text) => KeyBindings::parse(&text),
+            Err(_) => {
+                let bindings = KeyBindings::default();
+                let _ = fs::write(&path, bindings.to_config());
+                bindings
+            }
+        }
+    }
+}
+
+/// Last key pressed, used to ignore the operating system's key repeat
+#[derive(Default)]
+pub struct HeldKey {
+    pub key: Option<VirtualKeyCode>,
+}
+
+/// Drops repeated presses of a key until it has been let go
+pub fn ignore_repeats(ctx: &mut Rltk, held: &mut HeldKey) {
+    if let Some(key) = held.key {
+        if !INPUT.lock().is_key_pressed(key) {
+            held.key = None;
+        }
+    }
+    if let Some(key) = ctx.key {
+        if held.key == Some(key) {
+            ctx.key = None;
+        } else {
+            held.key = Some(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_round_trip() {
+        let defaults = KeyBindings::default();
+        let parsed = KeyBindings::parse(&defaults.to_config());
+        assert_eq!(parsed.bindings, defaults.bindings);
+    }
+
+    #[test]
+    fn remapped_keys_round_trip() {
+        let bindings = KeyBindings::parse("Explore = Tab, F\nPickup = Comma\n");
+        assert_eq!(bindings.keys_for(Action::Explore), vec!["Tab", "F"]);
+        let parsed = KeyBindings::parse(&bindings.to_config());
+        for action in Action::ALL.iter() {
+            assert_eq!(parsed.keys_for(*action), bindings.keys_for(*action));
+        }
+    }
+
+    #[test]
+    fn missing_actions_keep_defaults() {
+        let bindings = KeyBindings::parse("Pickup = Comma\n");
+        assert_eq!(bindings.keys_for(Action::Pickup), vec!["Comma"]);
+        assert_eq!(bindings.keys_for(Action::Explore), vec!["X"]);
+        assert_eq!(bindings.action(VirtualKeyCode::X), Some(Action::Explore));
+        assert_eq!(bindings.action(VirtualKeyCode::G), None);
+    }
+
+    #[test]
+    fn malformed_and_unknown_lines_are_skipped() {
+        let text = "# comment\n\
+                    \n\
+                    not a binding\n\
+                    Teleport = T\n\
+                    = Q\n\
+                    Explore = Tab, NoSuchKey, ,\n";
+        let bindings = KeyBindings::parse(text);
+        assert_eq!(bindings.keys_for(Action::Explore), vec!["Tab"]);
+        assert_eq!(bindings.action(VirtualKeyCode::T), None);
+        assert_eq!(
+            bindings.action(VirtualKeyCode::Q),
+            Some(Action::MoveNorthWest)
+        );
+    }
+
+    #[test]
+    fn empty_value_unbinds_action() {
+        let bindings = KeyBindings::parse("Explore =\n");
+        assert!(bindings.keys_for(Action::Explore).is_empty());
+        assert_eq!(bindings.key_for(Action::Explore), "?");
+    }
+
+    #[test]
+    fn play_and_menu_keys_are_separate() {
+        let bindings = KeyBindings::default();
+        assert_eq!(
+            bindings.menu_action(VirtualKeyCode::Up),
+            Some(Action::MenuUp)
+        );
+        assert_eq!(bindings.menu_action(VirtualKeyCode::X), None);
+        assert_eq!(
+            bindings.action(VirtualKeyCode::Escape),
+            Some(Action::OpenMenu)
+        );
+        assert_eq!(
+            bindings.menu_action(VirtualKeyCode::Escape),
+            Some(Action::CloseMenu)
+        );
+    }
+}
@@ -1,16 +1,14 @@
 //! Attack of the Blobs
 //! Roguelike game binary
-//! 
+//!
 //! Jordan Malubay CS410 - June 2021
 
-use rltk::{GameState, Point, Rltk, RGB};
+use rltk::{GameState, Point, RandomNumberGenerator, Rltk};
 use specs::prelude::*;
 mod components;
 pub use components::*;
 mod map;
 pub use map::*;
-mod map_index;
-pub use map_index::*;
 mod player;
 use player::*;
 mod rect;
@@ -24,33 +22,123 @@ pub use melee::*;
 mod damage_system;
 pub use damage_system::*;
 mod gui;
+pub use gui::Minimap;
 mod inventory;
 pub use inventory::*;
+mod spawner;
+pub use spawner::*;
+mod initiative;
+pub use initiative::*;
+mod realtime;
+pub use realtime::{GameMode, RealTimeClock};
+mod config;
+mod keybindings;
+pub use keybindings::{Action, HeldKey, KeyBindings};
+mod settings;
+pub use settings::{Difficulty, Settings};
+mod gamelog;
+pub use gamelog::GameLog;
+mod camera;
+mod score;
+pub use camera::Camera;
+mod theme;
+pub use score::{HighScoreTable, RunStats};
+pub use theme::Themes;
+mod particle_system;
+pub use particle_system::{ParticleBuilder, ParticleSpawnSystem};
+mod events;
+pub use events::{EventQueue, GameEvent};
+mod achievements;
+pub use achievements::Achievements;
+mod flow_map;
+pub use flow_map::{FlowMap, FlowMapSystem};
+mod swarm;
+pub use swarm::{Swarm, SwarmSystem};
+mod senses;
+pub use senses::{Senses, SensesSystem};
+mod regen;
+pub use regen::RegenSystem;
 
 /// States used to control the flow of the game
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
     AwaitingInput,
     PreRun,
-    PlayerTurn,
-    BlobTurn,
-    Menu,
+    Ticking,
+    Menu { selection: usize },
+    MainMenu { selection: usize },
+    Options { selection: usize, in_game: bool },
+    GameOver { victory: bool },
+    HighScores,
 }
 
-/// World is the Entity Control System 
+/// World is the Entity Control System
 pub struct State {
     pub ecs: World,
 }
 
 /// Set and run the World control systems from each module
 impl State {
+    /// Registers every component and inserts what the title screen needs,
+    /// the config files are loaded over the defaults by main
+    fn new() -> State {
+        let mut gs = State { ecs: World::new() };
+
+        // Start all the compenent systems in the gamestate
+        gs.ecs.register::<Position>();
+        gs.ecs.register::<Renderable>();
+        gs.ecs.register::<Player>();
+        gs.ecs.register::<Viewshed>();
+        gs.ecs.register::<Blob>();
+        gs.ecs.register::<Name>();
+        gs.ecs.register::<BlocksTile>();
+        gs.ecs.register::<CombatStats>();
+        gs.ecs.register::<WantsToMelee>();
+        gs.ecs.register::<SufferDamage>();
+        gs.ecs.register::<Item>();
+        gs.ecs.register::<Heal>();
+        gs.ecs.register::<WantsToPickupItem>();
+        gs.ecs.register::<Inventory>();
+        gs.ecs.register::<Initiative>();
+        gs.ecs.register::<MyTurn>();
+        gs.ecs.register::<SpeedEffect>();
+        gs.ecs.register::<Cooldown>();
+        gs.ecs.register::<AutoTravel>();
+        gs.ecs.register::<AutoExplore>();
+        gs.ecs.register::<ParticleLifetime>();
+        gs.ecs.register::<Regeneration>();
+        gs.ecs.register::<AutoRest>();
+
+        // Start on the title screen, the world is built when a new game starts
+        gs.ecs.insert(RunState::MainMenu { selection: 0 });
+        gs.ecs.insert(GameMode::TurnBased);
+        gs.ecs.insert(Settings::default());
+        gs.ecs.insert(HeldKey::default());
+        gs.ecs.insert(RealTimeClock::default());
+        gs.ecs.insert(KeyBindings::default());
+        gs.ecs.insert(HighScoreTable::default());
+        gs.ecs.insert(Minimap::default());
+        gs.ecs.insert(Themes::default());
+        gs.ecs.insert(ParticleBuilder::default());
+        gs.ecs.insert(EventQueue::default());
+        gs
+    }
+
     fn run_systems(&mut self) {
+        let mut initiative = InitiativeSystem {};
+        initiative.run_now(&self.ecs);
         let mut vis = VisibilitySystem {};
         vis.run_now(&self.ecs);
+        let mut regen = RegenSystem {};
+        regen.run_now(&self.ecs);
+        let mut senses = SensesSystem {};
+        senses.run_now(&self.ecs);
+        let mut flow = FlowMapSystem {};
+        flow.run_now(&self.ecs);
+        let mut swarm = SwarmSystem {};
+        swarm.run_now(&self.ecs);
         let mut mob = BlobAi {};
         mob.run_now(&self.ecs);
-        let mut mapindex = MapIndexingSystem {};
-        mapindex.run_now(&self.ecs);
         let mut melee = MeleeCombatSystem {};
         melee.run_now(&self.ecs);
         let mut damage = DamageSystem {};
@@ -60,28 +148,111 @@ impl State {
 
         self.ecs.maintain();
     }
-}
 
+    /// Clears the world and builds a new map, player and blobs
+    fn new_game(&mut self) {
+        self.ecs.delete_all();
+        self.ecs.maintain();
 
-impl GameState for State {
-    /// Each tick represents a round of turns within the game
-    fn tick(&mut self, ctx: &mut Rltk) {
-        // Clear the screen and draw the map
-        ctx.cls();
+        // The difficulty is fixed for the whole run
+        let difficulty = self.ecs.fetch::<Settings>().difficulty;
+        self.ecs.insert(difficulty);
+
+        // Use the seed from the settings so runs can be replayed
+        let seed = match self.ecs.fetch::<Settings>().seed {
+            0 => RandomNumberGenerator::new().next_u64(),
+            seed => seed,
+        };
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        self.ecs.insert(RunStats::new(seed, difficulty));
+
+        // Generate map and player start location in one of the rooms
+        let mut map = Map::map_gen(&mut rng);
+        let (player_x, player_y) = map.rooms[0].center();
+        let player_entity = spawner::player(&mut self.ecs, player_x, player_y);
+        map.move_entity(player_entity, map.index(player_x, player_y), false);
+
+        // Spawn blobs and items from the spawn table
+        spawner::spawn_level(&mut self.ecs, &mut map, 1, &mut rng);
+
+        self.ecs.insert(Senses::new(&map));
+        self.ecs.insert(map);
+        self.ecs.insert(player_entity);
+        self.ecs.insert(Point::new(player_x, player_y));
+        self.ecs.insert(RealTimeClock::default());
+        self.ecs.insert(GameLog::default());
+        self.ecs.insert(EventQueue::default());
+        self.ecs.insert(Achievements::default());
+        self.ecs.insert(FlowMap::default());
+        self.ecs.insert(Swarm::default());
+    }
+
+    /// Keeps ticking until the player has enough energy to act
+    /// The dead are removed every tick so they never get another turn
+    fn tick_until_player_turn(&mut self) -> RunState {
+        let mut runstate = RunState::Ticking;
+        while runstate == RunState::Ticking {
+            self.run_systems();
+            damage_system::delete_the_dead(&mut self.ecs);
+            runstate = *self.ecs.fetch::<RunState>();
+        }
+        runstate
+    }
+
+    /// A game can be continued once the world has been built
+    fn game_started(&self) -> bool {
+        self.ecs.has_value::<Map>()
+    }
+
+    /// The run is won once every crystal is collected and lost when the player dies
+    fn run_over(&self) -> Option<bool> {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let dead = self
+            .ecs
+            .read_storage::<CombatStats>()
+            .get(player_entity)
+            .is_none_or(|stats| stats.hp < 1);
+        if dead {
+            return Some(false);
+        }
+        if self.ecs.fetch::<RunStats>().crystals_collected >= BlobKind::BOSSES.len() as i32 {
+            return Some(true);
+        }
+        None
+    }
+
+    /// Draws the map, entities and HUD
+    fn draw_game(&self, ctx: &mut Rltk) {
         draw_map(&self.ecs, ctx);
 
-        // Get all the entities and draw them
+        // Get all the entities in view and draw them, particles go on top
         {
+            let camera = Camera::new(&self.ecs);
             let positions = self.ecs.read_storage::<Position>();
             let renderables = self.ecs.read_storage::<Renderable>();
-            for (pos, render) in (&positions, &renderables).join() {
-                ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
+            let particles = self.ecs.read_storage::<ParticleLifetime>();
+            for (pos, render, _) in (&positions, &renderables, !&particles).join() {
+                if let Some((x, y)) = camera.to_screen(pos.x, pos.y) {
+                    ctx.set(x, y, render.fg, render.bg, render.glyph)
+                }
+            }
+            for (pos, render, _) in (&positions, &renderables, &particles).join() {
+                if let Some((x, y)) = camera.to_screen(pos.x, pos.y) {
+                    ctx.set(x, y, render.fg, render.bg, render.glyph)
+                }
             }
         }
 
         // Draw the HUD
         gui::draw_ui(&self.ecs, ctx);
+        gui::draw_minimap(&self.ecs, ctx);
+        gui::draw_tooltips(&self.ecs, ctx);
+    }
+}
 
+impl GameState for State {
+    /// Each tick represents a round of turns within the game
+    fn tick(&mut self, ctx: &mut Rltk) {
         // Set the run state
         let mut newrunstate;
         {
@@ -89,6 +260,32 @@ impl GameState for State {
             newrunstate = *runstate;
         }
 
+        // Apply the display and input settings
+        {
+            let settings = self.ecs.fetch::<Settings>();
+            ctx.post_scanlines = settings.scanlines;
+            ctx.post_screenburn = settings.scanlines;
+            if !settings.key_repeat {
+                keybindings::ignore_repeats(ctx, &mut self.ecs.write_resource::<HeldKey>());
+            }
+        }
+
+        // Clear the screen and draw the map unless on the title screen
+        ctx.cls();
+        match newrunstate {
+            RunState::MainMenu { .. }
+            | RunState::Options { in_game: false, .. }
+            | RunState::HighScores => {}
+            _ => self.draw_game(ctx),
+        }
+
+        // Real-time cooldowns and the world clock run on frame time during play
+        if matches!(newrunstate, RunState::AwaitingInput | RunState::Ticking)
+            && *self.ecs.fetch::<GameMode>() == GameMode::RealTime
+        {
+            realtime::advance(&mut self.ecs, ctx.frame_time_ms);
+        }
+
         // Check the run state
         match newrunstate {
             RunState::PreRun => {
@@ -97,18 +294,171 @@ impl GameState for State {
             }
             RunState::AwaitingInput => {
                 newrunstate = player_input(self, ctx);
+                if newrunstate == RunState::Ticking {
+                    self.ecs.write_resource::<RunStats>().turns += 1;
+                }
+
+                // In real-time the world moves on if the player waits too long
+                if *self.ecs.fetch::<GameMode>() == GameMode::RealTime {
+                    if newrunstate == RunState::Ticking {
+                        realtime::reset_clock(&mut self.ecs);
+                    } else if realtime::take_step(&mut self.ecs) {
+                        newrunstate = RunState::Ticking;
+                    }
+                }
             }
-            RunState::PlayerTurn => {
-                self.run_systems();
-                newrunstate = RunState::BlobTurn;
+            RunState::Ticking => {
+                newrunstate = self.tick_until_player_turn();
             }
-            RunState::BlobTurn => {
-                self.run_systems();
-                newrunstate = RunState::AwaitingInput;
+            RunState::Menu { selection } => {
+                let options: Vec<String> = gui::GameMenuEntry::ALL
+                    .iter()
+                    .map(|entry| entry.label())
+                    .collect();
+                let result = gui::draw_menu(
+                    ctx,
+                    &self.ecs.fetch::<KeyBindings>(),
+                    "Menu",
+                    &options,
+                    selection,
+                );
+                match result {
+                    gui::MenuResult::NoResponse => {}
+                    gui::MenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::MenuResult::Highlight(selection) => {
+                        newrunstate = RunState::Menu { selection }
+                    }
+                    gui::MenuResult::Selected(selection) => {
+                        newrunstate = match gui::GameMenuEntry::ALL[selection] {
+                            gui::GameMenuEntry::Resume => RunState::AwaitingInput,
+                            gui::GameMenuEntry::Options => RunState::Options {
+                                selection: 0,
+                                in_game: true,
+                            },
+                            gui::GameMenuEntry::MainMenu => RunState::MainMenu { selection: 0 },
+                            gui::GameMenuEntry::Quit => std::process::exit(0),
+                        }
+                    }
+                }
             }
-            RunState::Menu => {
-                if gui::show_menu(ctx) == gui::MenuResult::Cancel {
-                    newrunstate = RunState::AwaitingInput;
+            RunState::MainMenu { selection } => {
+                let entries = gui::main_menu_entries(self.game_started());
+                let options: Vec<String> = entries.iter().map(|entry| entry.label()).collect();
+                let result = gui::draw_menu(
+                    ctx,
+                    &self.ecs.fetch::<KeyBindings>(),
+                    "Attack of the Blobs",
+                    &options,
+                    selection,
+                );
+                match result {
+                    gui::MenuResult::NoResponse => {}
+                    gui::MenuResult::Cancel => {
+                        if self.game_started() {
+                            newrunstate = RunState::AwaitingInput;
+                        }
+                    }
+                    gui::MenuResult::Highlight(selection) => {
+                        newrunstate = RunState::MainMenu { selection }
+                    }
+                    gui::MenuResult::Selected(selection) => match entries[selection] {
+                        gui::MainMenuEntry::NewGame => {
+                            self.new_game();
+                            newrunstate = RunState::PreRun;
+                        }
+                        gui::MainMenuEntry::Continue => newrunstate = RunState::AwaitingInput,
+                        gui::MainMenuEntry::Options => {
+                            newrunstate = RunState::Options {
+                                selection: 0,
+                                in_game: false,
+                            }
+                        }
+                        gui::MainMenuEntry::HighScores => newrunstate = RunState::HighScores,
+                        gui::MainMenuEntry::Quit => std::process::exit(0),
+                    },
+                }
+            }
+            RunState::Options { selection, in_game } => {
+                let options: Vec<String> = {
+                    let settings = self.ecs.fetch::<Settings>();
+                    gui::OptionsEntry::ALL
+                        .iter()
+                        .map(|entry| entry.label(&settings))
+                        .collect()
+                };
+                let result = gui::draw_menu(
+                    ctx,
+                    &self.ecs.fetch::<KeyBindings>(),
+                    "Options",
+                    &options,
+                    selection,
+                );
+                // Go back to whichever menu opened the options
+                let back = if in_game {
+                    RunState::Menu { selection: 0 }
+                } else {
+                    RunState::MainMenu { selection: 0 }
+                };
+                match result {
+                    gui::MenuResult::NoResponse => {}
+                    gui::MenuResult::Cancel => newrunstate = back,
+                    gui::MenuResult::Highlight(selection) => {
+                        newrunstate = RunState::Options { selection, in_game }
+                    }
+                    gui::MenuResult::Selected(selection) => {
+                        let entry = gui::OptionsEntry::ALL[selection];
+                        if entry == gui::OptionsEntry::Back {
+                            newrunstate = back;
+                        } else {
+                            if entry == gui::OptionsEntry::RealTime {
+                                realtime::toggle_mode(&mut self.ecs);
+                            }
+                            let mode = *self.ecs.fetch::<GameMode>();
+                            let mut settings = self.ecs.write_resource::<Settings>();
+                            match entry {
+                                gui::OptionsEntry::RealTime => {
+                                    settings.realtime = mode == GameMode::RealTime
+                                }
+                                gui::OptionsEntry::Scanlines => {
+                                    settings.scanlines = !settings.scanlines
+                                }
+                                gui::OptionsEntry::Difficulty => settings.next_difficulty(),
+                                gui::OptionsEntry::Theme => {
+                                    settings.next_theme(&self.ecs.fetch::<Themes>())
+                                }
+                                gui::OptionsEntry::KeyRepeat => {
+                                    settings.key_repeat = !settings.key_repeat
+                                }
+                                gui::OptionsEntry::LogSize => settings.next_log_size(),
+                                gui::OptionsEntry::Back => {}
+                            }
+                            settings.save();
+                        }
+                    }
+                }
+            }
+            RunState::GameOver { victory } => {
+                let result = gui::show_game_over(
+                    ctx,
+                    &self.ecs.fetch::<KeyBindings>(),
+                    victory,
+                    &self.ecs.fetch::<RunStats>(),
+                    &self.ecs.fetch::<HighScoreTable>(),
+                );
+                // The finished run can't be continued
+                if result != gui::MenuResult::NoResponse {
+                    self.ecs.remove::<Map>();
+                    newrunstate = RunState::MainMenu { selection: 0 };
+                }
+            }
+            RunState::HighScores => {
+                let result = gui::show_high_scores(
+                    ctx,
+                    &self.ecs.fetch::<KeyBindings>(),
+                    &self.ecs.fetch::<HighScoreTable>(),
+                );
+                if result != gui::MenuResult::NoResponse {
+                    newrunstate = RunState::MainMenu { selection: 0 };
                 }
             }
         }
@@ -118,146 +468,106 @@ impl GameState for State {
             *runwriter = newrunstate;
         }
         damage_system::delete_the_dead(&mut self.ecs);
+
+        // Let the log, score, particles and achievements know what happened
+        events::dispatch(&mut self.ecs);
+
+        // Effects are created and faded every frame, even while waiting for input
+        let mut particles = ParticleSpawnSystem {};
+        particles.run_now(&self.ecs);
+        particle_system::cull_dead_particles(&mut self.ecs, ctx);
+        self.ecs.maintain();
+
+        // End the run and record the score
+        if matches!(
+            newrunstate,
+            RunState::PreRun | RunState::AwaitingInput | RunState::Ticking
+        ) {
+            if let Some(victory) = self.run_over() {
+                {
+                    let stats = self.ecs.fetch::<RunStats>();
+                    self.ecs
+                        .write_resource::<HighScoreTable>()
+                        .record(&stats, victory);
+                }
+                *self.ecs.write_resource::<RunState>() = RunState::GameOver { victory };
+            }
+        }
     }
 }
 
 fn main() -> rltk::BError {
     // Initialize a new window
     use rltk::RltkBuilder;
-    let mut context = RltkBuilder::simple80x50()
-        .with_title("Roguelike Tutorial")
+    // Scanlines are applied every tick from the settings
+    let context = RltkBuilder::simple80x50()
+        .with_title("Attack of the Blobs")
         .build()?;
-    context.with_post_scanlines(true);
 
     // Initialize the gamestate
-    let mut gs = State { ecs: World::new() };
-
-    // Start all the compenent systems in the gamestate
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Blob>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<CombatStats>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<SufferDamage>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<Heal>();
-    gs.ecs.register::<WantsToPickupItem>();
-    gs.ecs.register::<Inventory>();
-
-    // Generate map and player start location in one of the rooms
-    let map = Map::map_gen();
-    let (player_x, player_y) = map.rooms[0].center();
-
-    // Spawn the Player
-    let player_entity = gs
-        .ecs
-        .create_entity()
-        .with(Position {
-            x: player_x,
-            y: player_y,
-        })
-        .with(Renderable {
-            glyph: rltk::to_cp437('@'),
-            fg: RGB::named(rltk::YELLOW),
-            bg: RGB::named(rltk::BLACK),
-        })
-        .with(Player {})
-        .with(Viewshed {
-            visible_tiles: Vec::new(),
-            range: 8,
-        })
-        .with(Name {
-            name: "Player".to_string(),
-        })
-        .with(CombatStats { max_hp: 10, hp: 10 })
-        .build();
-
-    // Spawn blobs, first 4 are 'boss' blobs, any after are regular
-    let mut rng = rltk::RandomNumberGenerator::new();
-    for i in 0..8 {
-        //Ensure blobs are outside of rooms
-        let (mut x, mut y);
-        loop {
-            let mut inside = false;
-            x = rng.roll_dice(1, 79);
-            y = rng.roll_dice(1, 42);
-            for room in map.rooms.iter() {
-                if room.inside((x, y)) {
-                    inside = true;
-                    break;
-                }
-            }
-            if !inside {
-                break;
-            }
-        }
+    let mut gs = State::new();
 
-        // Select the main colored blobs or generic grey blobs
-        let glyph: rltk::FontCharType;
-        let name: String;
-        let color: RGB;
-        match i {
-            //Important Blobs
-            1 => {
-                glyph = rltk::to_cp437('O');
-                name = "RED".to_string();
-                color = RGB::named(rltk::RED)
-            }
-            2 => {
-                glyph = rltk::to_cp437('O');
-                name = "BLUE".to_string();
-                color = RGB::named(rltk::BLUE)
-            }
-            3 => {
-                glyph = rltk::to_cp437('O');
-                name = "PURPLE".to_string();
-                color = RGB::named(rltk::PURPLE)
-            }
-            4 => {
-                glyph = rltk::to_cp437('O');
-                name = "YELLOW".to_string();
-                color = RGB::named(rltk::YELLOW)
-            }
-            // Extra Blobs
-            _ => {
-                glyph = rltk::to_cp437('O');
-                name = "GREY".to_string();
-                color = RGB::named(rltk::GREY)
-            }
-        }
+    // Load the saved settings
+    let settings = Settings::load();
+    gs.ecs.insert(if settings.realtime {
+        GameMode::RealTime
+    } else {
+        GameMode::TurnBased
+    });
+    gs.ecs.insert(settings);
+    gs.ecs.insert(KeyBindings::load());
+    gs.ecs.insert(HighScoreTable::load());
+    gs.ecs.insert(Themes::load());
 
-        // Create each blob
-        gs.ecs
-            .create_entity()
-            .with(Position { x, y })
-            .with(Renderable {
-                glyph,
-                fg: color,
-                bg: RGB::named(rltk::BLACK),
-            })
-            .with(Viewshed {
-                visible_tiles: Vec::new(),
-                range: 8,
-            })
-            .with(Blob {})
-            .with(Name {
-                name: format!("{} #{}", &name, i),
-            })
-            .with(BlocksTile {})
-            .with(CombatStats { max_hp: 1, hp: 1 })
-            .build();
+    // Run the game
+    rltk::main_loop(context, gs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The end of every frame runs on the title screen before any game is built
+    #[test]
+    fn title_screen_frame_runs_on_a_new_world() {
+        let mut gs = State::new();
+        damage_system::delete_the_dead(&mut gs.ecs);
+        events::dispatch(&mut gs.ecs);
+        let mut particles = ParticleSpawnSystem {};
+        particles.run_now(&gs.ecs);
+        gs.ecs.maintain();
+        assert!(!gs.game_started());
     }
 
-    // Add the map, player and set the initial run state
-    gs.ecs.insert(map);
-    gs.ecs.insert(player_entity);
-    gs.ecs.insert(RunState::PreRun);
-    gs.ecs.insert(Point::new(player_x, player_y));
+    /// A blob killed on one tick is gone before the next, so its drop lands where it died
+    #[test]
+    fn dead_blobs_drop_where_they_die() {
+        let mut gs = State::new();
+        gs.new_game();
+        let (blob, x, y) = {
+            let entities = gs.ecs.entities();
+            let blobs = gs.ecs.read_storage::<Blob>();
+            let positions = gs.ecs.read_storage::<Position>();
+            (&entities, &blobs, &positions)
+                .join()
+                .map(|(entity, _blob, pos)| (entity, pos.x, pos.y))
+                .next()
+                .expect("A blob was spawned")
+        };
+        gs.ecs
+            .write_storage::<CombatStats>()
+            .get_mut(blob)
+            .expect("Blobs have stats")
+            .hp = 0;
 
-    // Run the game
-    rltk::main_loop(context, gs)
+        *gs.ecs.write_resource::<RunState>() = RunState::Ticking;
+        gs.tick_until_player_turn();
+        assert!(!gs.ecs.is_alive(blob));
+
+        let items = gs.ecs.read_storage::<Item>();
+        let positions = gs.ecs.read_storage::<Position>();
+        assert!((&items, &positions)
+            .join()
+            .any(|(_item, pos)| pos.x == x && pos.y == y));
+    }
 }
@@ -1,40 +1,64 @@
 //! Constructs and displays the map as well as
-//! controls the list occupied tiles and their contents 
+//! controls the list occupied tiles and their contents
 
-use super::Rect;
-use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator, Rltk, RGB};
+use super::{Camera, Rect, Settings, Themes};
+use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator, Rltk};
 use specs::prelude::*;
+use std::collections::HashMap;
 
-/// Adjust these to the terminal size in main
-/// MAPWIDTH is terminal width
-/// MAPHEIGHT is terminal height - 7
-const MAPWIDTH: usize = 80;
-const MAPHEIGHT: usize = 43;
+/// The map can be bigger than the screen, the camera
+/// scrolls to keep the player in view
+const MAPWIDTH: usize = 120;
+const MAPHEIGHT: usize = 64;
 const MAPCOUNT: usize = MAPHEIGHT * MAPWIDTH;
 
 ///Map tiles for drawing all background elements
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum TileType {
-    VWall,
-    HWall,
+    Wall,
     Floor,
     HDoor,
     VDoor,
-    Cur,
-    Cul,
-    Clr,
-    Cll,
+}
+
+impl TileType {
+    pub const ALL: [TileType; 4] = [
+        TileType::Wall,
+        TileType::Floor,
+        TileType::HDoor,
+        TileType::VDoor,
+    ];
+
+    /// Walls can't be walked or seen through
+    pub fn is_wall(&self) -> bool {
+        *self == TileType::Wall
+    }
+
+    pub fn is_door(&self) -> bool {
+        matches!(self, TileType::HDoor | TileType::VDoor)
+    }
 }
 
 /// All Map info vectors are width * height
+/// Terrain that can't be walked through is `blocked`, tiles holding a
+/// blocking entity are `occupied`, the two are kept apart so each caller
+/// can check only what it needs
+/// Whatever spawns, moves or removes an entity updates the index itself
+/// with `move_entity` and `remove_entity`, nothing rescans the entities
+/// Room interiors are `safe`, blobs can't enter them or attack into them
 #[derive(Default)]
 pub struct Map {
     pub tiles: Vec<TileType>,
     pub rooms: Vec<Rect>,
     pub width: i32,
     pub height: i32,
+    pub blocked: Vec<bool>,
     pub occupied: Vec<bool>,
+    pub safe: Vec<bool>,
     pub tile_content: Vec<Vec<Entity>>,
+    pub revealed_tiles: Vec<bool>,
+    /// Tile each indexed entity is on and whether it blocks
+    entity_tiles: HashMap<Entity, (usize, bool)>,
 }
 
 impl Map {
@@ -55,59 +79,45 @@ impl Map {
                     if x == room.x1 + 1 + ((room.x2 - room.x1) / 2) {
                         self.tiles[idx] = TileType::HDoor
                     }
-                    // Upper left corner
-                    else if x == room.x1 + 1 && y == room.y1 + 1 {
-                        self.tiles[idx] = TileType::Cul
-                    }
-                    // Upper right corner
-                    else if x == room.x2 && y == room.y1 + 1 {
-                        self.tiles[idx] = TileType::Cur
-                    }
-                    // Lower left corner
-                    else if x == room.x1 + 1 && y == room.y2 {
-                        self.tiles[idx] = TileType::Cll
-                    }
-                    // Lower right corner
-                    else if x == room.x2 && y == room.y2 {
-                        self.tiles[idx] = TileType::Clr
-                    }
-                    // Horizonal Wall
+                    // Horizontal wall, the corners are joined when drawn
                     else {
-                        self.tiles[idx] = TileType::HWall;
+                        self.tiles[idx] = TileType::Wall;
                     }
                 } else if x == room.x1 + 1 || x == room.x2 {
                     // Vertical doors
                     if y == room.y1 + 1 + ((room.y2 - room.y1) / 2) {
                         self.tiles[idx] = TileType::VDoor
                     }
-                    // Vertical Wall
+                    // Vertical wall
                     else {
-                        self.tiles[idx] = TileType::VWall;
+                        self.tiles[idx] = TileType::Wall;
                     }
                 }
             }
         }
     }
 
-    /// Places 5 rooms randomly around the map
-    pub fn map_gen() -> Map {
+    /// Places 8 rooms randomly around the map
+    pub fn map_gen(rng: &mut RandomNumberGenerator) -> Map {
         // Initialize map data
         let mut map = Map {
             tiles: vec![TileType::Floor; MAPCOUNT],
             rooms: Vec::new(),
             width: MAPWIDTH as i32,
             height: MAPHEIGHT as i32,
+            blocked: vec![false; MAPCOUNT],
             occupied: vec![false; MAPCOUNT],
+            safe: vec![false; MAPCOUNT],
             tile_content: vec![Vec::new(); MAPCOUNT],
+            revealed_tiles: vec![false; MAPCOUNT],
+            entity_tiles: HashMap::new(),
         };
 
         // Adjust to changes the number of rooms and size
-        const NUM_ROOMS: i32 = 4;
+        const NUM_ROOMS: i32 = 8;
         const WIDTH: i32 = 9;
         const HEIGHT: i32 = 7;
 
-        let mut rng = RandomNumberGenerator::new();
-
         // Randomly place all the rooms with no overlap
         let mut i = 0;
         while i < NUM_ROOMS {
@@ -126,135 +136,258 @@ impl Map {
                 i += 1;
             }
         }
+        map.populate_blocked();
+        map.populate_safe();
         map
     }
 
-    /// Any tile containing impassable objects are occupied
-    pub fn occupied(&mut self) {
-        for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.occupied[i] = *tile == TileType::HWall
-                || *tile == TileType::VWall
-                || *tile == TileType::Cur
-                || *tile == TileType::Cul
-                || *tile == TileType::Clr
-                || *tile == TileType::Cll
+    /// Everything inside a room's walls is safe from the blobs
+    fn populate_safe(&mut self) {
+        let interiors: Vec<usize> = self
+            .rooms
+            .iter()
+            .flat_map(|room| {
+                (room.y1 + 2..room.y2)
+                    .flat_map(move |y| (room.x1 + 2..room.x2).map(move |x| (x, y)))
+            })
+            .map(|(x, y)| self.index(x, y))
+            .collect();
+        for idx in interiors {
+            self.safe[idx] = true;
+        }
+    }
+
+    /// Walls block the tile, the terrain doesn't change once the map is built
+    fn populate_blocked(&mut self) {
+        for (i, tile) in self.tiles.iter().enumerate() {
+            self.blocked[i] = tile.is_wall()
+        }
+    }
+
+    /// Blocked by either the terrain or an entity
+    pub fn is_blocked(&self, idx: usize) -> bool {
+        self.blocked[idx] || self.occupied[idx]
+    }
+
+    /// Places an entity on a tile, moving it off the tile it was on before
+    pub fn move_entity(&mut self, entity: Entity, idx: usize, blocks: bool) {
+        if self.entity_tiles.get(&entity) == Some(&(idx, blocks)) {
+            return;
+        }
+        self.remove_entity(entity);
+        self.tile_content[idx].push(entity);
+        self.entity_tiles.insert(entity, (idx, blocks));
+        if blocks {
+            self.occupied[idx] = true;
+        }
+    }
+
+    /// Takes an entity off the map, its tile stays occupied
+    /// only if another blocking entity is still on it
+    pub fn remove_entity(&mut self, entity: Entity) {
+        if let Some((idx, _)) = self.entity_tiles.remove(&entity) {
+            self.tile_content[idx].retain(|other| *other != entity);
+            let entity_tiles = &self.entity_tiles;
+            self.occupied[idx] = self.tile_content[idx]
+                .iter()
+                .any(|other| entity_tiles.get(other).is_some_and(|(_, blocks)| *blocks));
+        }
+    }
+
+    /// Checks the tile type at x,y, anything off the map counts as a wall
+    fn tile_at(&self, x: i32, y: i32) -> TileType {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return TileType::Wall;
         }
+        self.tiles[self.index(x, y)]
+    }
+
+    /// Diagonal steps can't squeeze between wall corners
+    /// or go through a door frame at an angle
+    pub fn diagonal_allowed(&self, x: i32, y: i32, delta_x: i32, delta_y: i32) -> bool {
+        if delta_x == 0 || delta_y == 0 {
+            return true;
+        }
+        !self.tile_at(x + delta_x, y).is_wall()
+            && !self.tile_at(x, y + delta_y).is_wall()
+            && !self.tile_at(x, y).is_door()
+            && !self.tile_at(x + delta_x, y + delta_y).is_door()
+    }
+
+    /// Melee only reaches the eight tiles around the attacker,
+    /// diagonally only when no wall corner or door frame is in the way
+    pub fn can_melee(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        let (delta_x, delta_y) = (to.0 - from.0, to.1 - from.1);
+        if (delta_x == 0 && delta_y == 0) || delta_x.abs() > 1 || delta_y.abs() > 1 {
+            return false;
+        }
+        !self.tile_at(from.0, from.1).is_wall()
+            && !self.tile_at(to.0, to.1).is_wall()
+            && self.diagonal_allowed(from.0, from.1, delta_x, delta_y)
+    }
+
+    /// Tiles anything can move onto, the top row and left column are never used
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 1 && x < self.width && y >= 1 && y < self.height
     }
 
     /// Checks if a move is inside the map or into an occupied tile
     fn valid_move(&self, x: i32, y: i32) -> bool {
-        if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
+        if !self.in_bounds(x, y) {
             return false;
         }
-        let idx = self.index(x, y);
-        !self.occupied[idx]
+        !self.is_blocked(self.index(x, y))
     }
 
-    /// Clears the entities from the content vector
-    pub fn clear_content_index(&mut self) {
-        for content in self.tile_content.iter_mut() {
-            content.clear();
+    /// Like `valid_move` but only walls block, entities are ignored
+    fn walkable(&self, x: i32, y: i32) -> bool {
+        if !self.in_bounds(x, y) {
+            return false;
         }
+        !self.blocked[self.index(x, y)]
     }
-}
 
-/// Draw all the tiles on the map
-pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
-    let map = ecs.fetch::<Map>();
-    let mut y = 0;
-    let mut x = 0;
-    for tile in map.tiles.iter() {
-        // Render a tile depending upon the tile type
-        match tile {
-            TileType::HDoor => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('─'),
-                );
-            }
-            TileType::VDoor => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('│'),
-                );
-            }
-            TileType::Floor => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.5, 0.5, 0.5),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437(' '),
-                );
-            }
-            TileType::VWall => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('║'),
-                );
-            }
-            TileType::HWall => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('═'),
-                );
-            }
-            TileType::Cur => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('╗'),
-                );
-            }
-            TileType::Cul => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('╔'),
-                );
-            }
-            TileType::Clr => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('╝'),
-                );
-            }
-            TileType::Cll => {
-                ctx.set(
-                    x,
-                    y,
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    RGB::from_f32(0.0, 0.0, 0.0),
-                    rltk::to_cp437('╚'),
-                );
+    /// Tiles reachable in one step from idx that pass the check,
+    /// diagonals cost more and can't cut corners
+    fn exits(
+        &self,
+        idx: usize,
+        passable: impl Fn(i32, i32) -> bool,
+    ) -> rltk::SmallVec<[(usize, f32); 10]> {
+        let mut moves = rltk::SmallVec::new();
+        let x = idx as i32 % self.width;
+        let y = idx as i32 / self.width;
+        let w = self.width as usize;
+
+        // Cardinal directions
+        if passable(x - 1, y) {
+            moves.push((idx - 1, 1.0))
+        };
+        if passable(x + 1, y) {
+            moves.push((idx + 1, 1.0))
+        };
+        if passable(x, y - 1) {
+            moves.push((idx - w, 1.0))
+        };
+        if passable(x, y + 1) {
+            moves.push((idx + w, 1.0))
+        };
+
+        // Diagonals, which can't cut corners
+        for (delta_x, delta_y) in [(-1, -1), (1, -1), (-1, 1), (1, 1)].iter() {
+            if passable(x + delta_x, y + delta_y) && self.diagonal_allowed(x, y, *delta_x, *delta_y)
+            {
+                moves.push((self.index(x + delta_x, y + delta_y), 1.45))
             }
         }
+        moves
+    }
+
+    /// Exits a blob can take, which never lead into a safe room
+    pub fn blob_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        self.exits(idx, |x, y| {
+            self.valid_move(x, y) && !self.safe[self.index(x, y)]
+        })
+    }
+
+    /// Exits through terrain alone, used for maps shared by every blob
+    /// They lead out of a safe room but never into one, so a map spread from
+    /// inside a room reaches the blobs outside without leading them through rooms
+    pub fn blob_terrain_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        let inside = self.safe[idx];
+        self.exits(idx, |x, y| {
+            self.walkable(x, y) && (inside || !self.safe[self.index(x, y)])
+        })
+    }
 
-        // Move the coordinates
-        x += 1;
-        if x > 79 {
-            x = 0;
-            y += 1;
+    /// Picks the box drawing glyph that joins a wall to the walls and doors
+    /// next to it, isolated walls return None and use the theme's glyph
+    pub fn wall_glyph(&self, x: i32, y: i32) -> Option<rltk::FontCharType> {
+        let joins = |x: i32, y: i32| {
+            x >= 0 && x < self.width && y >= 0 && y < self.height && {
+                let tile = self.tiles[self.index(x, y)];
+                tile.is_wall() || tile.is_door()
+            }
+        };
+        // One bit for each neighbour, north, south, west, east
+        let mut mask = 0;
+        if joins(x, y - 1) {
+            mask |= 1;
+        }
+        if joins(x, y + 1) {
+            mask |= 2;
+        }
+        if joins(x - 1, y) {
+            mask |= 4;
+        }
+        if joins(x + 1, y) {
+            mask |= 8;
         }
+        let glyph = match mask {
+            0 => return None,
+            1..=3 => '║',
+            4 | 8 | 12 => '═',
+            5 => '╝',
+            6 => '╗',
+            9 => '╚',
+            10 => '╔',
+            7 => '╣',
+            11 => '╠',
+            13 => '╩',
+            14 => '╦',
+            _ => '╬',
+        };
+        Some(rltk::to_cp437(glyph))
+    }
+}
+
+/// Draw the tiles in view of the camera using the chosen theme
+pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let themes = ecs.fetch::<Themes>();
+    let theme = themes.get(&ecs.fetch::<Settings>().theme);
+    let camera = Camera::new(ecs);
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        // Only draw the tiles the camera can see
+        let (x, y) = match camera.to_screen(idx as i32 % map.width, idx as i32 / map.width) {
+            Some(screen) => screen,
+            None => continue,
+        };
+        let style = theme.style(*tile);
+        let glyph = match tile {
+            TileType::Wall => map
+                .wall_glyph(idx as i32 % map.width, idx as i32 / map.width)
+                .unwrap_or(style.glyph),
+            _ => style.glyph,
+        };
+        ctx.set(x, y, style.fg, style.bg, glyph);
+    }
+}
+
+/// View of the map that only follows the terrain and keeps out of the safe
+/// rooms, for blob maps that have to stay valid while entities move around
+pub struct BlobTerrain<'a> {
+    pub map: &'a Map,
+}
+
+impl BaseMap for BlobTerrain<'_> {
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        self.map.blob_terrain_exits(idx)
+    }
+}
+
+/// View of the map for blob paths, which go around the safe rooms
+pub struct BlobPaths<'a> {
+    pub map: &'a Map,
+}
+
+impl BaseMap for BlobPaths<'_> {
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        self.map.blob_exits(idx)
     }
 }
 
@@ -267,7 +400,7 @@ impl Algorithm2D for Map {
 impl BaseMap for Map {
     /// Stop blobs from seeing the player when in rooms
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        self.tiles[idx] != TileType::Floor
     }
 
     /// Get distance for blobs when they move
@@ -280,24 +413,138 @@ impl BaseMap for Map {
 
     /// Check which directions are movable
     fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
-        let mut moves = rltk::SmallVec::new();
-        let x = idx as i32 % self.width;
-        let y = idx as i32 / self.width;
-        let w = self.width as usize;
+        self.exits(idx, |x, y| self.valid_move(x, y))
+    }
+}
 
-        // Cardinal directions
-        if self.valid_move(x - 1, y) {
-            moves.push((idx - 1, 1.0))
-        };
-        if self.valid_move(x + 1, y) {
-            moves.push((idx + 1, 1.0))
-        };
-        if self.valid_move(x, y - 1) {
-            moves.push((idx - w, 1.0))
-        };
-        if self.valid_move(x, y + 1) {
-            moves.push((idx + w, 1.0))
-        };
-        moves
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rltk::DijkstraMap;
+
+    /// A generated map, with the first room's interior checked in the tests
+    fn room_map() -> (Map, usize, usize) {
+        let map = Map::map_gen(&mut RandomNumberGenerator::seeded(3));
+        let room = &map.rooms[0];
+        let inside = map.index(room.x1 + 2, room.y1 + 2);
+        let outside = map.index(room.x1, room.y1);
+        (map, inside, outside)
+    }
+
+    /// A small map drawn as text, `#` walls, `.` floor, `-` and `|` doors
+    fn drawn_map(rows: &[&str]) -> Map {
+        let tiles: Vec<TileType> = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| match c {
+                '#' => TileType::Wall,
+                '-' => TileType::HDoor,
+                '|' => TileType::VDoor,
+                _ => TileType::Floor,
+            })
+            .collect();
+        Map {
+            width: rows[0].len() as i32,
+            height: rows.len() as i32,
+            tiles,
+            ..Default::default()
+        }
+    }
+
+    /// Floor around a lone wall at 3,2 with a door below it at 3,3
+    fn melee_map() -> Map {
+        drawn_map(&["#######", "#.....#", "#..#..#", "#..-..#", "#######"])
+    }
+
+    #[test]
+    fn melee_reaches_adjacent_tiles() {
+        let map = melee_map();
+        assert!(map.can_melee((1, 1), (2, 1)));
+        assert!(map.can_melee((1, 2), (1, 1)));
+        assert!(map.can_melee((1, 1), (2, 2)));
+        assert!(map.can_melee((5, 2), (4, 1)));
+        assert!(map.can_melee((3, 3), (4, 3)));
+        assert!(!map.can_melee((1, 1), (1, 1)));
+        assert!(!map.can_melee((1, 1), (3, 1)));
+        assert!(!map.can_melee((1, 1), (2, 3)));
+    }
+
+    #[test]
+    fn melee_never_starts_or_ends_in_a_wall() {
+        let map = melee_map();
+        assert!(!map.can_melee((2, 2), (3, 2)));
+        assert!(!map.can_melee((3, 2), (4, 2)));
+        assert!(!map.can_melee((1, 1), (0, 0)));
+        assert!(!map.can_melee((0, 0), (1, 1)));
+    }
+
+    #[test]
+    fn diagonals_stop_at_wall_corners_and_door_frames() {
+        let map = melee_map();
+        assert!(map.diagonal_allowed(1, 1, 1, 1));
+        assert!(map.diagonal_allowed(2, 2, 0, 1));
+        // The wall at 3,2 is beside both of these steps
+        assert!(!map.diagonal_allowed(2, 2, 1, -1));
+        assert!(!map.diagonal_allowed(4, 2, -1, -1));
+        assert!(!map.can_melee((2, 2), (3, 1)));
+        // Into and out of the door at 3,3 at an angle
+        assert!(!map.diagonal_allowed(2, 2, 1, 1));
+        assert!(!map.diagonal_allowed(3, 3, 1, -1));
+        assert!(!map.can_melee((4, 2), (3, 3)));
+        assert!(!map.can_melee((3, 3), (2, 2)));
+    }
+
+    #[test]
+    fn walls_join_into_box_drawing_glyphs() {
+        let map = drawn_map(&["#####", "#.#.#", "#####", "#.#.#", "#####"]);
+        let glyph = |x, y| map.wall_glyph(x, y);
+        let cp437 = |c| Some(rltk::to_cp437(c));
+        assert_eq!(glyph(0, 0), cp437('╔'));
+        assert_eq!(glyph(4, 0), cp437('╗'));
+        assert_eq!(glyph(0, 4), cp437('╚'));
+        assert_eq!(glyph(4, 4), cp437('╝'));
+        assert_eq!(glyph(2, 0), cp437('╦'));
+        assert_eq!(glyph(2, 4), cp437('╩'));
+        assert_eq!(glyph(0, 2), cp437('╠'));
+        assert_eq!(glyph(4, 2), cp437('╣'));
+        assert_eq!(glyph(2, 2), cp437('╬'));
+        assert_eq!(glyph(1, 0), cp437('═'));
+        assert_eq!(glyph(0, 1), cp437('║'));
+    }
+
+    #[test]
+    fn isolated_walls_keep_the_theme_glyph() {
+        let map = drawn_map(&["...", ".#.", "...", "#|."]);
+        assert_eq!(map.wall_glyph(1, 1), None);
+        // Doors join walls too
+        assert_eq!(map.wall_glyph(0, 3), Some(rltk::to_cp437('═')));
+    }
+
+    #[test]
+    fn blob_maps_lead_out_of_rooms_but_not_in() {
+        let (map, inside, _) = room_map();
+        assert!(map.safe[inside]);
+        assert!(!map.blob_terrain_exits(inside).is_empty());
+        for idx in 0..map.tiles.len() {
+            if map.safe[idx] {
+                continue;
+            }
+            for (exit, _) in map.blob_terrain_exits(idx) {
+                assert!(!map.safe[exit], "{} leads into a room at {}", idx, exit);
+            }
+        }
+    }
+
+    #[test]
+    fn flow_from_a_room_reaches_outside() {
+        let (map, inside, outside) = room_map();
+        let dijkstra = DijkstraMap::new(
+            map.width,
+            map.height,
+            &[inside],
+            &BlobTerrain { map: &map },
+            1000.0,
+        );
+        assert!(dijkstra.map[outside] < f32::MAX);
     }
 }
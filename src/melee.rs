@@ -1,6 +1,8 @@
 //! Processes pending melee combat events
 
-use super::{CombatStats, Name, SufferDamage, WantsToMelee};
+use super::{
+    Blob, CombatStats, EventQueue, GameEvent, Map, Name, Position, SufferDamage, WantsToMelee,
+};
 use specs::prelude::*;
 
 pub struct MeleeCombatSystem {}
@@ -12,30 +14,66 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Name>,
         ReadStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, EventQueue>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Blob>,
     );
 
     ///Scan pending attacks and sent valid attacks to the damage system
+    /// Stale attacks and attacks that can't reach their target are dropped
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_melee, names, combat_stats, mut inflict_damage) = data;
+        let (
+            entities,
+            mut wants_melee,
+            names,
+            combat_stats,
+            mut inflict_damage,
+            positions,
+            mut events,
+            map,
+            blobs,
+        ) = data;
 
-        for (_entity, wants_melee, name, stats) in
-            (&entities, &wants_melee, &names, &combat_stats).join()
+        for (entity, wants_melee, name, stats, pos) in
+            (&entities, &wants_melee, &names, &combat_stats, &positions).join()
         {
-            // Check if attacker should be dead
-            if stats.hp > 0 {
-                let target_stats = combat_stats.get(wants_melee.target).unwrap();
-                
-                // Check if target should be dead
-                if target_stats.hp > 0 {
-                    let target_name = names.get(wants_melee.target).unwrap();
-                    println!(
-                        "{} hits {}, for {} hp.",
-                        &name.name, &target_name.name, 1
-                    );
-                    // Send to damage system
-                    SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, 1);
-                    
+            // Drop attacks on targets that were deleted since the intent was made
+            if !entities.is_alive(wants_melee.target) {
+                continue;
+            }
+            let (target_stats, target_name, target_pos) = match (
+                combat_stats.get(wants_melee.target),
+                names.get(wants_melee.target),
+                positions.get(wants_melee.target),
+            ) {
+                (Some(target_stats), Some(target_name), Some(target_pos)) => {
+                    (target_stats, target_name, target_pos)
                 }
+                _ => continue,
+            };
+
+            // Attacks only land on a neighbouring tile with no wall in between
+            if !map.can_melee((pos.x, pos.y), (target_pos.x, target_pos.y)) {
+                continue;
+            }
+
+            // Blobs can't reach anyone inside a safe room
+            if blobs.get(entity).is_some() && map.safe[map.index(target_pos.x, target_pos.y)] {
+                continue;
+            }
+
+            // Check if attacker or target should be dead
+            if stats.hp > 0 && target_stats.hp > 0 {
+                events.publish(GameEvent::Attacked {
+                    attacker: name.name.clone(),
+                    target: target_name.name.clone(),
+                    amount: 1,
+                    x: target_pos.x,
+                    y: target_pos.y,
+                });
+                // Send to damage system
+                SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, 1);
             }
         }
         // Clear any events that could not process
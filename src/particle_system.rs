@@ -0,0 +1,160 @@
+//! Particle system
+//! Game events request short lived effects for hits, deaths and pickups,
+//! they are created once per frame and fade out on their own timer
+
+use super::{GameEvent, Map, ParticleLifetime, Position, Renderable};
+use rltk::{FontCharType, Rltk, RGB};
+use specs::prelude::*;
+
+/// How long each kind of effect stays on screen
+pub const HIT_MS: f32 = 200.0;
+pub const DAMAGE_MS: f32 = 300.0;
+pub const SPLATTER_MS: f32 = 600.0;
+pub const SPARKLE_MS: f32 = 500.0;
+
+struct ParticleRequest {
+    x: i32,
+    y: i32,
+    fg: RGB,
+    glyph: FontCharType,
+    lifetime_ms: f32,
+}
+
+/// Pending particles, filled by the systems and emptied by `ParticleSpawnSystem`
+#[derive(Default)]
+pub struct ParticleBuilder {
+    requests: Vec<ParticleRequest>,
+}
+
+impl ParticleBuilder {
+    pub fn request(&mut self, x: i32, y: i32, fg: RGB, glyph: FontCharType, lifetime_ms: f32) {
+        self.requests.push(ParticleRequest {
+            x,
+            y,
+            fg,
+            glyph,
+            lifetime_ms,
+        });
+    }
+
+    /// Flashes hits, shows damage, splatters dead blobs and sparkles crystals
+    pub fn notify(&mut self, event: &GameEvent, map: &Map) {
+        match event {
+            GameEvent::Attacked { x, y, .. } => self.request(
+                *x,
+                *y,
+                RGB::named(rltk::ORANGE),
+                rltk::to_cp437('‼'),
+                HIT_MS,
+            ),
+            // Show the damage dealt just above the entity
+            GameEvent::Damaged { amount, x, y, .. } => {
+                let digit = std::char::from_digit((*amount).clamp(0, 9) as u32, 10).unwrap_or('*');
+                self.request(
+                    *x,
+                    *y - 1,
+                    RGB::named(rltk::RED),
+                    rltk::to_cp437(digit),
+                    DAMAGE_MS,
+                );
+            }
+            // Splatter the blob's color around where it died, but not onto walls
+            GameEvent::Died { color, x, y, .. } => {
+                for (delta_x, delta_y) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+                    let (x, y) = (x + delta_x, y + delta_y);
+                    if x < 0 || x >= map.width || y < 0 || y >= map.height {
+                        continue;
+                    }
+                    if !map.tiles[map.index(x, y)].is_wall() {
+                        self.request(x, y, *color, rltk::to_cp437('░'), SPLATTER_MS);
+                    }
+                }
+            }
+            // Crystals sparkle in their own color as they are picked up
+            GameEvent::ItemPickedUp {
+                crystal: true,
+                color,
+                x,
+                y,
+                ..
+            } => {
+                for (delta_x, delta_y) in [(-1, -1), (1, -1), (-1, 1), (1, 1)].iter() {
+                    self.request(
+                        x + delta_x,
+                        y + delta_y,
+                        *color,
+                        rltk::to_cp437('☼'),
+                        SPARKLE_MS,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct ParticleSpawnSystem {}
+
+impl<'a> System<'a> for ParticleSpawnSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, ParticleLifetime>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    /// Turns every pending request into a particle entity
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut positions, mut renderables, mut particles, mut builder) = data;
+        for request in builder.requests.drain(..) {
+            let particle = entities.create();
+            positions
+                .insert(
+                    particle,
+                    Position {
+                        x: request.x,
+                        y: request.y,
+                    },
+                )
+                .expect("Unable to insert position");
+            renderables
+                .insert(
+                    particle,
+                    Renderable {
+                        glyph: request.glyph,
+                        fg: request.fg,
+                        bg: RGB::named(rltk::BLACK),
+                    },
+                )
+                .expect("Unable to insert renderable");
+            particles
+                .insert(
+                    particle,
+                    ParticleLifetime {
+                        lifetime_ms: request.lifetime_ms,
+                    },
+                )
+                .expect("Unable to insert lifetime");
+        }
+    }
+}
+
+/// Counts down every particle by the frame time and deletes the expired ones
+/// Runs every frame so effects fade even while waiting for the player
+pub fn cull_dead_particles(ecs: &mut World, ctx: &Rltk) {
+    let mut dead: Vec<Entity> = Vec::new();
+    {
+        let mut particles = ecs.write_storage::<ParticleLifetime>();
+        let entities = ecs.entities();
+        for (entity, particle) in (&entities, &mut particles).join() {
+            particle.lifetime_ms -= ctx.frame_time_ms;
+            if particle.lifetime_ms < 0.0 {
+                dead.push(entity);
+            }
+        }
+    }
+    for particle in dead.iter() {
+        ecs.delete_entity(*particle).expect("Particle will not die");
+    }
+}
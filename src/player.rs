@@ -1,31 +1,325 @@
 //! Control the player entity movement, inventory, and player controls
-//! 
-use super::{Item, Map, Player, Position, RunState, State, WantsToPickupItem};
-use rltk::{Point, Rltk, VirtualKeyCode};
+//!
+use super::{
+    Action, AutoExplore, AutoRest, AutoTravel, Blob, BlocksTile, Camera, CombatStats, EventQueue,
+    GameEvent, GameLog, Item, KeyBindings, Map, Minimap, Player, Position, RunState, State,
+    Viewshed, WantsToMelee, WantsToPickupItem,
+};
+use rltk::{Point, Rltk};
 use specs::prelude::*;
-use std::cmp::{max, min};
 
 /// Compares new locations with all other entites and occupied tiles
+/// and moves the player on the map index when the move is valid
+/// Moving into a blob attacks it instead
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+    if let Some(target) = blob_to_attack(delta_x, delta_y, ecs) {
+        attack(ecs, target);
+        return;
+    }
+
+    let entities = ecs.entities();
     let mut positions = ecs.write_storage::<Position>();
-    let mut players = ecs.write_storage::<Player>();
-    let map = ecs.fetch::<Map>();
+    let players = ecs.read_storage::<Player>();
+    let blockers = ecs.read_storage::<BlocksTile>();
+    let mut map = ecs.write_resource::<Map>();
 
     // Scan other entities for conflcits
-    for (_player, pos) in (&mut players, &mut positions).join() {
-        let destination_idx = map.index(pos.x + delta_x, pos.y + delta_y);
-        // Scan for walls
-        if !map.occupied[destination_idx] {
-            pos.x = min(79, max(0, pos.x + delta_x));
-            pos.y = min(49, max(0, pos.y + delta_y));
+    for (entity, _player, pos) in (&entities, &players, &mut positions).join() {
+        let (x, y) = (pos.x + delta_x, pos.y + delta_y);
+        // Use the same bounds as pathing, the index would wrap or underflow past them
+        if !map.in_bounds(x, y) {
+            continue;
+        }
+        let destination_idx = map.index(x, y);
+        // Scan for walls and corners cut by diagonal moves
+        if !map.is_blocked(destination_idx) && map.diagonal_allowed(pos.x, pos.y, delta_x, delta_y)
+        {
+            pos.x = x;
+            pos.y = y;
+            map.move_entity(entity, destination_idx, blockers.get(entity).is_some());
             let mut ppos = ecs.write_resource::<Point>();
             // Update position on valid move
             ppos.x = pos.x;
-            ppos.y = pos.y
+            ppos.y = pos.y;
+            ecs.write_resource::<EventQueue>()
+                .publish(GameEvent::PlayerMoved { x: pos.x, y: pos.y });
         }
     }
 }
 
+/// Blob on the tile next to the player in the given direction, if it can be hit from here
+fn blob_to_attack(delta_x: i32, delta_y: i32, ecs: &World) -> Option<Entity> {
+    let player_pos = *ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let blobs = ecs.read_storage::<Blob>();
+    let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+    if !map.in_bounds(x, y) || !map.can_melee((player_pos.x, player_pos.y), (x, y)) {
+        return None;
+    }
+    map.tile_content[map.index(x, y)]
+        .iter()
+        .copied()
+        .find(|entity| blobs.get(*entity).is_some())
+}
+
+/// Sends the player's attack to the melee system
+fn attack(ecs: &mut World, target: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<WantsToMelee>()
+        .insert(player_entity, WantsToMelee { target })
+        .expect("Unable to insert attack");
+}
+
+/// Attacks the blob next to the player in the given direction,
+/// no turn is used when there is nothing there to hit
+fn try_attack(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    match blob_to_attack(delta_x, delta_y, ecs) {
+        Some(target) => {
+            attack(ecs, target);
+            RunState::Ticking
+        }
+        None => {
+            ecs.write_resource::<GameLog>()
+                .push("There is nothing there to attack.".to_string());
+            RunState::AwaitingInput
+        }
+    }
+}
+
+/// Blobs the player can currently see
+fn visible_blobs(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let entities = ecs.entities();
+    let blobs = ecs.read_storage::<Blob>();
+    let positions = ecs.read_storage::<Position>();
+
+    let mut seen = Vec::new();
+    if let Some(viewshed) = viewsheds.get(*player_entity) {
+        for (entity, _blob, pos) in (&entities, &blobs, &positions).join() {
+            if viewshed.visible_tiles.contains(&Point::new(pos.x, pos.y)) {
+                seen.push(entity);
+            }
+        }
+    }
+    seen
+}
+
+/// Finds a path to the target tile and sets the player travelling along it
+pub fn start_travel(ecs: &mut World, target_x: i32, target_y: i32) {
+    let path = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        if target_x < 0 || target_x >= map.width || target_y < 0 || target_y >= map.height {
+            return;
+        }
+        let target_idx = map.index(target_x, target_y);
+        if map.blocked[target_idx] {
+            return;
+        }
+        rltk::a_star_search(map.index(player_pos.x, player_pos.y), target_idx, &*map)
+    };
+    if !path.success || path.steps.len() < 2 {
+        return;
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    let hp = ecs
+        .read_storage::<CombatStats>()
+        .get(player_entity)
+        .map_or(0, |stats| stats.hp);
+    let seen = visible_blobs(ecs);
+    ecs.write_storage::<AutoTravel>()
+        .insert(
+            player_entity,
+            AutoTravel {
+                path: path.steps[1..].to_vec(),
+                hp,
+                seen,
+            },
+        )
+        .expect("Unable to insert travel");
+}
+
+/// Stops the player travelling
+pub fn cancel_travel(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<AutoTravel>().remove(player_entity);
+}
+
+/// Takes the next step of the travel path, returns false when travel stops
+fn travel_step(ecs: &mut World) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    let travel = match ecs.read_storage::<AutoTravel>().get(player_entity) {
+        Some(travel) => travel.clone(),
+        None => return false,
+    };
+
+    // Stop when hurt or when a new blob comes into view
+    let hp = ecs
+        .read_storage::<CombatStats>()
+        .get(player_entity)
+        .map_or(0, |stats| stats.hp);
+    let seen = visible_blobs(ecs);
+    if hp < travel.hp
+        || seen.iter().any(|blob| !travel.seen.contains(blob))
+        || travel.path.is_empty()
+    {
+        cancel_travel(ecs);
+        return false;
+    }
+
+    // Move toward the next tile on the path
+    let start = *ecs.fetch::<Point>();
+    let (next_x, next_y) = {
+        let map = ecs.fetch::<Map>();
+        (
+            travel.path[0] as i32 % map.width,
+            travel.path[0] as i32 / map.width,
+        )
+    };
+    try_move_player(next_x - start.x, next_y - start.y, ecs);
+
+    // Something is in the way
+    if *ecs.fetch::<Point>() == start {
+        cancel_travel(ecs);
+        return false;
+    }
+
+    if let Some(travel) = ecs.write_storage::<AutoTravel>().get_mut(player_entity) {
+        travel.path.remove(0);
+        travel.hp = hp;
+        travel.seen = seen;
+    }
+    true
+}
+
+/// Starts the player exploring on their own
+fn start_explore(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<AutoExplore>()
+        .insert(player_entity, AutoExplore {})
+        .expect("Unable to insert explore");
+}
+
+/// Stops the player exploring
+pub fn cancel_explore(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<AutoExplore>().remove(player_entity);
+}
+
+/// Starts resting, unless the player is already healed or a blob is in view
+pub fn start_rest(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let (hp, max_hp) = match ecs.read_storage::<CombatStats>().get(player_entity) {
+        Some(stats) => (stats.hp, stats.max_hp),
+        None => return,
+    };
+    if hp >= max_hp {
+        ecs.write_resource::<GameLog>()
+            .push("You are already at full health.".to_string());
+        return;
+    }
+    if !visible_blobs(ecs).is_empty() {
+        ecs.write_resource::<GameLog>()
+            .push("You can't rest with blobs nearby.".to_string());
+        return;
+    }
+    ecs.write_storage::<AutoRest>()
+        .insert(player_entity, AutoRest { hp })
+        .expect("Unable to insert rest");
+}
+
+/// Stops the player resting
+pub fn cancel_rest(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<AutoRest>().remove(player_entity);
+}
+
+/// Waits another turn, returns false once the player is healed,
+/// hurt or a blob comes into view
+fn rest_step(ecs: &mut World) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    let hp = ecs
+        .read_storage::<CombatStats>()
+        .get(player_entity)
+        .map(|stats| (stats.hp, stats.max_hp));
+    let rested = ecs
+        .read_storage::<AutoRest>()
+        .get(player_entity)
+        .map(|rest| rest.hp);
+    let (hp, max_hp, rested) = match (hp, rested) {
+        (Some((hp, max_hp)), Some(rested)) => (hp, max_hp, rested),
+        _ => return false,
+    };
+    if hp >= max_hp || hp < rested || !visible_blobs(ecs).is_empty() {
+        cancel_rest(ecs);
+        return false;
+    }
+    if let Some(rest) = ecs.write_storage::<AutoRest>().get_mut(player_entity) {
+        rest.hp = hp;
+    }
+    true
+}
+
+/// Steps downhill on a Dijkstra map toward the nearest unrevealed tile or item
+/// returns false once a blob is visible or there is nothing left to explore
+fn explore_step(ecs: &mut World) -> bool {
+    if !visible_blobs(ecs).is_empty() {
+        cancel_explore(ecs);
+        return false;
+    }
+
+    // Pick up anything the player is standing on
+    let player_pos = *ecs.fetch::<Point>();
+    let on_item = {
+        let items = ecs.read_storage::<Item>();
+        let positions = ecs.read_storage::<Position>();
+        (&items, &positions)
+            .join()
+            .any(|(_item, pos)| pos.x == player_pos.x && pos.y == player_pos.y)
+    };
+    if on_item {
+        get_item(ecs);
+        return true;
+    }
+
+    let next = {
+        let map = ecs.fetch::<Map>();
+        let items = ecs.read_storage::<Item>();
+        let positions = ecs.read_storage::<Position>();
+
+        // Every unrevealed open tile and item is a goal
+        let mut starts: Vec<usize> = (0..map.tiles.len())
+            .filter(|idx| !map.revealed_tiles[*idx] && !map.blocked[*idx])
+            .collect();
+        for (_item, pos) in (&items, &positions).join() {
+            starts.push(map.index(pos.x, pos.y));
+        }
+
+        let player_idx = map.index(player_pos.x, player_pos.y);
+        let dijkstra = rltk::DijkstraMap::new(map.width, map.height, &starts, &*map, 2000.0);
+        rltk::DijkstraMap::find_lowest_exit(&dijkstra, player_idx, &*map)
+            .filter(|exit| dijkstra.map[*exit] < f32::MAX)
+            .map(|exit| (exit as i32 % map.width, exit as i32 / map.width))
+    };
+
+    // Whole level has been explored
+    let (next_x, next_y) = match next {
+        Some(next) => next,
+        None => {
+            cancel_explore(ecs);
+            return false;
+        }
+    };
+    try_move_player(next_x - player_pos.x, next_y - player_pos.y, ecs);
+    if *ecs.fetch::<Point>() == player_pos {
+        cancel_explore(ecs);
+        return false;
+    }
+    true
+}
+
 /// Verifies the locations of the player and item then
 /// sends the pickup request to the wants to pickup system
 fn get_item(ecs: &mut World) {
@@ -63,25 +357,169 @@ fn get_item(ecs: &mut World) {
 
 /// Waits for player input and returns the running state
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    match ctx.key {
+    // Click a tile to travel there
+    if ctx.left_click {
+        let (mouse_x, mouse_y) = ctx.mouse_pos();
+        if Camera::in_view(mouse_x, mouse_y) {
+            let (target_x, target_y) = Camera::new(&gs.ecs).to_world(mouse_x, mouse_y);
+            start_travel(&mut gs.ecs, target_x, target_y);
+        }
+    }
+
+    // Keep travelling until a key is pressed
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    let travelling = gs
+        .ecs
+        .read_storage::<AutoTravel>()
+        .get(player_entity)
+        .is_some();
+    if travelling {
+        if ctx.key.is_some() {
+            cancel_travel(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        if travel_step(&mut gs.ecs) {
+            return RunState::Ticking;
+        }
+    }
+
+    // Keep exploring until a key is pressed
+    let exploring = gs
+        .ecs
+        .read_storage::<AutoExplore>()
+        .get(player_entity)
+        .is_some();
+    if exploring {
+        if ctx.key.is_some() {
+            cancel_explore(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        if explore_step(&mut gs.ecs) {
+            return RunState::Ticking;
+        }
+    }
+
+    // Keep resting until a key is pressed
+    let resting = gs
+        .ecs
+        .read_storage::<AutoRest>()
+        .get(player_entity)
+        .is_some();
+    if resting {
+        if ctx.key.is_some() {
+            cancel_rest(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        if rest_step(&mut gs.ecs) {
+            return RunState::Ticking;
+        }
+    }
+
+    // Look up the action bound to the key
+    let action = match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
-        Some(key) => match key {
-            // Open Menu
-            VirtualKeyCode::Escape => return RunState::Menu,
-            // Character Movement
-            VirtualKeyCode::A => try_move_player(-1, 0, &mut gs.ecs),
-            VirtualKeyCode::D => try_move_player(1, 0, &mut gs.ecs),
-            VirtualKeyCode::W => try_move_player(0, -1, &mut gs.ecs),
-            VirtualKeyCode::S => try_move_player(0, 1, &mut gs.ecs),
-            // Shoot
-            VirtualKeyCode::Up => todo!(),
-            VirtualKeyCode::Down => todo!(),
-            VirtualKeyCode::Left => todo!(),
-            VirtualKeyCode::Right => todo!(),
-            // Pickup item
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
-            _ => return RunState::AwaitingInput,
+        Some(key) => match gs.ecs.fetch::<KeyBindings>().action(key) {
+            Some(action) => action,
+            None => return RunState::AwaitingInput,
         },
+    };
+
+    match action {
+        // Open Menu
+        Action::OpenMenu => return RunState::Menu { selection: 0 },
+        // Character Movement
+        Action::MoveWest => try_move_player(-1, 0, &mut gs.ecs),
+        Action::MoveEast => try_move_player(1, 0, &mut gs.ecs),
+        Action::MoveNorth => try_move_player(0, -1, &mut gs.ecs),
+        Action::MoveSouth => try_move_player(0, 1, &mut gs.ecs),
+        // Diagonal Movement
+        Action::MoveNorthWest => try_move_player(-1, -1, &mut gs.ecs),
+        Action::MoveNorthEast => try_move_player(1, -1, &mut gs.ecs),
+        Action::MoveSouthWest => try_move_player(-1, 1, &mut gs.ecs),
+        Action::MoveSouthEast => try_move_player(1, 1, &mut gs.ecs),
+        // Attack
+        Action::ShootNorth => return try_attack(0, -1, &mut gs.ecs),
+        Action::ShootSouth => return try_attack(0, 1, &mut gs.ecs),
+        Action::ShootWest => return try_attack(-1, 0, &mut gs.ecs),
+        Action::ShootEast => return try_attack(1, 0, &mut gs.ecs),
+        // Pickup item
+        Action::Pickup => get_item(&mut gs.ecs),
+        // Let a turn pass
+        Action::Wait => {}
+        // Wait until healed or disturbed
+        Action::Rest => {
+            start_rest(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        // Show or hide the minimap, this doesn't use a turn
+        Action::ToggleMinimap => {
+            let mut minimap = gs.ecs.write_resource::<Minimap>();
+            minimap.visible = !minimap.visible;
+            return RunState::AwaitingInput;
+        }
+        // Explore automatically
+        Action::Explore => {
+            start_explore(&mut gs.ecs);
+            return RunState::AwaitingInput;
+        }
+        _ => return RunState::AwaitingInput,
+    }
+    RunState::Ticking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A new game with the first blob moved next to the player
+    fn game_with_blob(delta_x: i32, delta_y: i32) -> (State, Entity) {
+        let mut gs = State::new();
+        gs.new_game();
+        let player_pos = *gs.ecs.fetch::<Point>();
+        let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+        let blob = {
+            let entities = gs.ecs.entities();
+            let blobs = gs.ecs.read_storage::<Blob>();
+            (&entities, &blobs)
+                .join()
+                .map(|(entity, _blob)| entity)
+                .next()
+                .expect("A blob was spawned")
+        };
+        {
+            let mut positions = gs.ecs.write_storage::<Position>();
+            let pos = positions.get_mut(blob).expect("Blobs have a position");
+            pos.x = x;
+            pos.y = y;
+            let mut map = gs.ecs.write_resource::<Map>();
+            let idx = map.index(x, y);
+            map.move_entity(blob, idx, true);
+        }
+        (gs, blob)
+    }
+
+    #[test]
+    fn moving_into_a_blob_attacks_it() {
+        for (delta_x, delta_y) in [(1, 0), (-1, 0), (0, 1), (1, 1), (-1, -1)].iter() {
+            let (mut gs, blob) = game_with_blob(*delta_x, *delta_y);
+            let start = *gs.ecs.fetch::<Point>();
+            try_move_player(*delta_x, *delta_y, &mut gs.ecs);
+
+            let player_entity = *gs.ecs.fetch::<Entity>();
+            let attack = gs
+                .ecs
+                .read_storage::<WantsToMelee>()
+                .get(player_entity)
+                .map(|attack| attack.target);
+            assert_eq!(attack, Some(blob));
+            assert_eq!(*gs.ecs.fetch::<Point>(), start);
+        }
+    }
+
+    #[test]
+    fn attack_keys_need_a_blob() {
+        let (mut gs, _blob) = game_with_blob(1, 0);
+        assert!(try_attack(-1, 0, &mut gs.ecs) == RunState::AwaitingInput);
+        assert!(try_attack(1, 0, &mut gs.ecs) == RunState::Ticking);
     }
-    RunState::PlayerTurn
 }
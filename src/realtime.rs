@@ -0,0 +1,73 @@
+//! Real-time mode
+//! Advances the world on a fixed timestep instead of waiting
+//! for the player, blobs are held back by action cooldowns
+
+use super::Cooldown;
+use specs::prelude::*;
+
+/// Time the world waits for the player before it moves on without them
+pub const TIMESTEP_MS: f32 = 400.0;
+
+/// Time a blob has to wait between actions in real-time mode
+pub const ACTION_COOLDOWN_MS: f32 = 800.0;
+
+/// Controls whether the world waits for the player
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum GameMode {
+    TurnBased,
+    RealTime,
+}
+
+/// Time since the world last advanced
+#[derive(Default)]
+pub struct RealTimeClock {
+    pub elapsed_ms: f32,
+}
+
+/// Counts down the cooldowns and the world clock, called once every frame of play
+pub fn advance(ecs: &mut World, frame_time_ms: f32) {
+    {
+        let entities = ecs.entities();
+        let mut cooldowns = ecs.write_storage::<Cooldown>();
+        let mut ready: Vec<Entity> = Vec::new();
+        for (entity, cooldown) in (&entities, &mut cooldowns).join() {
+            cooldown.ms -= frame_time_ms;
+            if cooldown.ms <= 0.0 {
+                ready.push(entity);
+            }
+        }
+        for entity in ready {
+            cooldowns.remove(entity);
+        }
+    }
+
+    ecs.write_resource::<RealTimeClock>().elapsed_ms += frame_time_ms;
+}
+
+/// Returns true once a full timestep has passed, using it up
+pub fn take_step(ecs: &mut World) -> bool {
+    let mut clock = ecs.write_resource::<RealTimeClock>();
+    if clock.elapsed_ms >= TIMESTEP_MS {
+        clock.elapsed_ms -= TIMESTEP_MS;
+        return true;
+    }
+    false
+}
+
+/// Restarts the timestep after the player acts
+pub fn reset_clock(ecs: &mut World) {
+    ecs.write_resource::<RealTimeClock>().elapsed_ms = 0.0;
+}
+
+/// Switches between turn-based and real-time play
+pub fn toggle_mode(ecs: &mut World) {
+    let mut mode = ecs.write_resource::<GameMode>();
+    *mode = match *mode {
+        GameMode::TurnBased => GameMode::RealTime,
+        GameMode::RealTime => GameMode::TurnBased,
+    };
+    // Cooldowns only count down in real-time so drop any that are left
+    if *mode == GameMode::TurnBased {
+        ecs.write_storage::<Cooldown>().clear();
+    }
+}
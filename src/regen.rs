@@ -0,0 +1,53 @@
+//! Regeneration system
+//! Heals entities a point at a time as they take turns,
+//! resting in a safe room heals much faster
+
+use super::{CombatStats, Map, MyTurn, Position, Regeneration, RunState};
+use specs::prelude::*;
+
+/// Turns it takes to heal one point out in the open
+const REGEN_TURNS: i32 = 20;
+
+/// Turns it takes to heal one point inside a safe room
+const SAFE_REGEN_TURNS: i32 = 4;
+
+pub struct RegenSystem {}
+
+impl<'a> System<'a> for RegenSystem {
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, RunState>,
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Regeneration>,
+        WriteStorage<'a, CombatStats>,
+    );
+
+    /// Counts the turns of every hurt entity and heals it once enough have passed
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, runstate, turns, positions, mut regens, mut stats) = data;
+
+        // Only count ticks where Initiative handed out turns, it runs while ticking
+        // and leaves the state Ticking, or AwaitingInput once the player has a turn
+        if !matches!(*runstate, RunState::Ticking | RunState::AwaitingInput) {
+            return;
+        }
+
+        for (_turn, pos, regen, stats) in (&turns, &positions, &mut regens, &mut stats).join() {
+            if stats.hp >= stats.max_hp || stats.hp < 1 {
+                regen.turns = 0;
+                continue;
+            }
+            regen.turns += 1;
+            let needed = if map.safe[map.index(pos.x, pos.y)] {
+                SAFE_REGEN_TURNS
+            } else {
+                REGEN_TURNS
+            };
+            if regen.turns >= needed {
+                regen.turns = 0;
+                stats.hp += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Score tracking
+//! Counts what happens during a run and keeps the best
+//! runs in a high score file in the config folder
+
+use super::{config, Difficulty, GameEvent};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File in the config folder holding the high scores
+const SCORES_FILE: &str = "scores.cfg";
+
+/// Number of runs kept in the high score table
+pub const MAX_SCORES: usize = 10;
+
+/// Everything counted toward the score of the current run
+#[derive(Clone, Debug)]
+pub struct RunStats {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub blobs_killed: i32,
+    pub crystals_collected: i32,
+    pub turns: i32,
+    pub damage_taken: i32,
+}
+
+impl RunStats {
+    pub fn new(seed: u64, difficulty: Difficulty) -> RunStats {
+        RunStats {
+            seed,
+            difficulty,
+            blobs_killed: 0,
+            crystals_collected: 0,
+            turns: 0,
+            damage_taken: 0,
+        }
+    }
+
+    /// Kills and crystals are worth the most, damage taken costs points
+    pub fn score(&self, victory: bool) -> i32 {
+        let bonus = if victory { 500 } else { 0 };
+        (self.blobs_killed * 10 + self.crystals_collected * 100 + self.turns
+            - self.damage_taken * 5
+            + bonus)
+            .max(0)
+    }
+
+    /// Counts kills, crystals and damage to the player as they happen
+    pub fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Damaged {
+                amount,
+                to_player: true,
+                ..
+            } => self.damage_taken += amount,
+            GameEvent::Died { .. } => self.blobs_killed += 1,
+            GameEvent::ItemPickedUp {
+                crystal: true,
+                by_player: true,
+                ..
+            } => self.crystals_collected += 1,
+            _ => {}
+        }
+    }
+}
+
+/// A finished run in the high score table
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScore {
+    pub score: i32,
+    pub date: String,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub victory: bool,
+}
+
+impl HighScore {
+    fn to_line(&self) -> String {
+        format!(
+            "{}|{}|{}|{:?}|{}",
+            self.score, self.date, self.seed, self.difficulty, self.victory
+        )
+    }
+
+    fn parse(line: &str) -> Option<HighScore> {
+        let parts: Vec<&str> = line.trim().split('|').collect();
+        if parts.len() != 5 {
+            return None;
+        }
+        Some(HighScore {
+            score: parts[0].parse().ok()?,
+            date: parts[1].to_string(),
+            seed: parts[2].parse().ok()?,
+            difficulty: Difficulty::ALL
+                .iter()
+                .copied()
+                .find(|difficulty| format!("{:?}", difficulty) == parts[3])?,
+            victory: parts[4].parse().ok()?,
+        })
+    }
+}
+
+/// Today's date as YYYY-MM-DD
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs()) as i64;
+    date_from_secs(secs)
+}
+
+/// Converts seconds since 1970 to a YYYY-MM-DD calendar date
+fn date_from_secs(secs: i64) -> String {
+    let days = secs.div_euclid(86400) + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Best runs so far, best first
+#[derive(Default)]
+pub struct HighScoreTable {
+    pub scores: Vec<HighScore>,
+}
+
+impl HighScoreTable {
+    /// Loads the saved high scores
+    pub fn load() -> HighScoreTable {
+        let text = fs::read_to_string(config::config_file(SCORES_FILE)).unwrap_or_default();
+        let mut scores: Vec<HighScore> = text.lines().filter_map(HighScore::parse).collect();
+        scores.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        scores.truncate(MAX_SCORES);
+        HighScoreTable { scores }
+    }
+
+    /// Adds a finished run to the table, keeping only the best runs, and saves it
+    pub fn record(&mut self, stats: &RunStats, victory: bool) {
+        self.scores.push(HighScore {
+            score: stats.score(victory),
+            date: today(),
+            seed: stats.seed,
+            difficulty: stats.difficulty,
+            victory,
+        });
+        self.scores
+            .sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.scores.truncate(MAX_SCORES);
+
+        let lines: Vec<String> = self.scores.iter().map(|score| score.to_line()).collect();
+        let _ = fs::write(config::config_file(SCORES_FILE), lines.join("\n") + "\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(date_from_secs(0), "1970-01-01");
+        assert_eq!(date_from_secs(86_399), "1970-01-01");
+        assert_eq!(date_from_secs(-86_400), "1969-12-31");
+        assert_eq!(date_from_secs(951_782_400), "2000-02-29");
+        assert_eq!(date_from_secs(1_709_164_800), "2024-02-29");
+        assert_eq!(date_from_secs(1_735_689_599), "2024-12-31");
+        assert_eq!(date_from_secs(4_102_444_800), "2100-01-01");
+    }
+
+    #[test]
+    fn score_line_round_trip() {
+        let entry = HighScore {
+            score: 640,
+            date: "2024-02-29".to_string(),
+            seed: 42,
+            difficulty: Difficulty::Hard,
+            victory: true,
+        };
+        assert_eq!(HighScore::parse(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let lines = [
+            "",
+            "# comment",
+            "640|2024-02-29|42|Hard",
+            "640|2024-02-29|42|Hard|true|extra",
+            "lots|2024-02-29|42|Hard|true",
+            "640|2024-02-29|-1|Hard|true",
+            "640|2024-02-29|42|Impossible|true",
+            "640|2024-02-29|42|Hard|yes",
+        ];
+        for line in lines.iter() {
+            assert_eq!(HighScore::parse(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn score_never_goes_negative() {
+        let mut stats = RunStats::new(0, Difficulty::Normal);
+        stats.damage_taken = 100;
+        assert_eq!(stats.score(false), 0);
+        stats.damage_taken = 0;
+        stats.blobs_killed = 2;
+        stats.crystals_collected = 1;
+        stats.turns = 30;
+        assert_eq!(stats.score(false), 150);
+        assert_eq!(stats.score(true), 650);
+    }
+}
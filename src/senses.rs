@@ -0,0 +1,106 @@
+//! Senses
+//! Noise and scent let blobs find a player they can't see, noise spreads
+//! from loud actions through open tiles and scent is left wherever the
+//! player walks, both fade every time the player takes a turn
+
+use super::{BlobTerrain, GameEvent, Map, Point, RunState};
+use rltk::DijkstraMap;
+use specs::prelude::*;
+
+/// Loudness of each action, sound drops by one for every tile it travels
+const MOVE_NOISE: i32 = 6;
+const FIGHT_NOISE: i32 = 20;
+
+/// Player turns a scent lasts after the player leaves the tile
+const SCENT_TURNS: i32 = 30;
+
+/// Sound and scent left on every tile of the map
+pub struct Senses {
+    pub noise: Vec<i32>,
+    pub scent: Vec<i32>,
+}
+
+impl Senses {
+    pub fn new(map: &Map) -> Senses {
+        Senses {
+            noise: vec![0; map.tiles.len()],
+            scent: vec![0; map.tiles.len()],
+        }
+    }
+
+    /// Spreads a sound out from a tile, walls stop it but it leaks through doors
+    /// Sound is never carried through a safe room, blobs following it would get stuck at the door
+    pub fn emit(&mut self, map: &Map, x: i32, y: i32, loudness: i32) {
+        let source = map.index(x, y);
+        let terrain = BlobTerrain { map };
+        let distances =
+            DijkstraMap::new(map.width, map.height, &[source], &terrain, loudness as f32);
+        for (idx, distance) in distances.map.iter().enumerate() {
+            if *distance < f32::MAX {
+                let heard = loudness - *distance as i32;
+                self.noise[idx] = self.noise[idx].max(heard);
+            }
+        }
+    }
+
+    /// Moving makes a little noise and fighting makes a lot
+    pub fn notify(&mut self, event: &GameEvent, map: &Map) {
+        match event {
+            GameEvent::PlayerMoved { x, y } => self.emit(map, *x, *y, MOVE_NOISE),
+            GameEvent::Attacked {
+                attacker,
+                target,
+                x,
+                y,
+                ..
+            } if attacker == "Player" || target == "Player" => self.emit(map, *x, *y, FIGHT_NOISE),
+            _ => {}
+        }
+    }
+
+    /// Next tile toward the loudest sound, or along the freshest scent
+    /// if there is nothing to hear
+    pub fn track(&self, map: &Map, from: usize) -> Option<usize> {
+        let exits = map.blob_exits(from);
+        let toward = |layer: &Vec<i32>| {
+            exits
+                .iter()
+                .map(|(idx, _)| *idx)
+                .filter(|idx| layer[*idx] > layer[from])
+                .max_by_key(|idx| layer[*idx])
+        };
+        toward(&self.noise).or_else(|| toward(&self.scent))
+    }
+}
+
+pub struct SensesSystem {}
+
+impl<'a> System<'a> for SensesSystem {
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, Senses>,
+    );
+
+    /// Fades the noise and scent and marks the player's tile
+    /// Several ticks pass for every player turn, fading by turns keeps a sound
+    /// around long enough for the blobs to act on it
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, player_pos, runstate, mut senses) = data;
+
+        // Initiative has just given the player a turn
+        if *runstate != RunState::AwaitingInput {
+            return;
+        }
+
+        for noise in senses.noise.iter_mut() {
+            *noise = (*noise - 1).max(0);
+        }
+        for scent in senses.scent.iter_mut() {
+            *scent = (*scent - 1).max(0);
+        }
+        let idx = map.index(player_pos.x, player_pos.y);
+        senses.scent[idx] = SCENT_TURNS;
+    }
+}
@@ -0,0 +1,259 @@
+//! Settings
+//! Options chosen on the options screen, saved to a file in
+//! the config folder and applied when the game starts
+
+use super::{config, Themes};
+use std::fs;
+
+/// File in the config folder holding the settings
+const SETTINGS_FILE: &str = "settings.cfg";
+
+/// Most lines the message log can show in the HUD
+pub const MAX_LOG_SIZE: usize = 4;
+
+/// Difficulty used for new games
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+/// Numbers that change with the difficulty
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifficultyPreset {
+    pub extra_blobs: i32,
+    pub blob_hp_bonus: i32,
+    pub blob_sight: i32,
+    pub player_hp: i32,
+    pub heal_amount: i32,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Harder games have more, tougher blobs that see further
+    /// and a weaker player who heals less
+    pub fn preset(&self) -> DifficultyPreset {
+        match self {
+            Difficulty::Easy => DifficultyPreset {
+                extra_blobs: 2,
+                blob_hp_bonus: 0,
+                blob_sight: 6,
+                player_hp: 15,
+                heal_amount: 6,
+            },
+            Difficulty::Normal => DifficultyPreset {
+                extra_blobs: 4,
+                blob_hp_bonus: 0,
+                blob_sight: 8,
+                player_hp: 10,
+                heal_amount: 4,
+            },
+            Difficulty::Hard => DifficultyPreset {
+                extra_blobs: 7,
+                blob_hp_bonus: 1,
+                blob_sight: 10,
+                player_hp: 8,
+                heal_amount: 2,
+            },
+        }
+    }
+}
+
+/// Moves to the option after the current one, wrapping back to the start
+fn next<T: PartialEq + Copy>(all: &[T], current: T) -> T {
+    let idx = all
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0);
+    all[(idx + 1) % all.len()]
+}
+
+fn parse_option<T: Copy + std::fmt::Debug>(all: &[T], value: &str) -> Option<T> {
+    all.iter()
+        .copied()
+        .find(|option| format!("{:?}", option) == value)
+}
+
+pub struct Settings {
+    pub realtime: bool,
+    pub scanlines: bool,
+    pub difficulty: Difficulty,
+    pub theme: String,
+    pub key_repeat: bool,
+    pub log_size: usize,
+    pub seed: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            realtime: false,
+            scanlines: true,
+            difficulty: Difficulty::Normal,
+            theme: "Classic".to_string(),
+            key_repeat: true,
+            log_size: MAX_LOG_SIZE,
+            seed: 0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn next_difficulty(&mut self) {
+        self.difficulty = next(&Difficulty::ALL, self.difficulty);
+    }
+
+    pub fn next_theme(&mut self, themes: &Themes) {
+        self.theme = themes.next(&self.theme);
+    }
+
+    /// Cycles the log between one line and the most the HUD can fit
+    pub fn next_log_size(&mut self) {
+        self.log_size = self.log_size % MAX_LOG_SIZE + 1;
+    }
+
+    /// Reads `setting = value` lines, anything missing or invalid keeps its default
+    pub fn parse(text: &str) -> Settings {
+        let mut settings = Settings::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            match name {
+                "realtime" => settings.realtime = value.parse().unwrap_or(settings.realtime),
+                "scanlines" => settings.scanlines = value.parse().unwrap_or(settings.scanlines),
+                "difficulty" => {
+                    settings.difficulty =
+                        parse_option(&Difficulty::ALL, value).unwrap_or(settings.difficulty)
+                }
+                "theme" if !value.is_empty() => settings.theme = value.to_string(),
+                "key_repeat" => settings.key_repeat = value.parse().unwrap_or(settings.key_repeat),
+                "log_size" => {
+                    settings.log_size = value
+                        .parse()
+                        .unwrap_or(settings.log_size)
+                        .clamp(1, MAX_LOG_SIZE)
+                }
+                "seed" => settings.seed = value.parse().unwrap_or(settings.seed),
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    /// Writes the settings in the same format `parse` reads
+    pub fn to_config(&self) -> String {
+        format!(
+            "# Attack of the Blobs settings\n\
+             realtime = {}\n\
+             scanlines = {}\n\
+             difficulty = {:?}\n\
+             theme = {}\n\
+             key_repeat = {}\n\
+             log_size = {}\n\
+             # Use the same seed to play the same map, 0 picks a new one every game\n\
+             seed = {}\n",
+            self.realtime,
+            self.scanlines,
+            self.difficulty,
+            self.theme,
+            self.key_repeat,
+            self.log_size,
+            self.seed
+        )
+    }
+
+    /// Loads the settings from the config folder, or the defaults if there are none
+    pub fn load() -> Settings {
+        match fs::read_to_string(config::config_file(SETTINGS_FILE)) {
+            Ok(text) => Settings::parse(&text),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let _ = fs::write(config::config_file(SETTINGS_FILE), self.to_config());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_round_trip() {
+        let defaults = Settings::default();
+        let parsed = Settings::parse(&defaults.to_config());
+        assert_eq!(parsed.to_config(), defaults.to_config());
+    }
+
+    #[test]
+    fn changed_settings_round_trip() {
+        let settings = Settings {
+            realtime: true,
+            scanlines: false,
+            difficulty: Difficulty::Hard,
+            theme: "Amber".to_string(),
+            key_repeat: false,
+            log_size: 2,
+            seed: 12345,
+        };
+        let parsed = Settings::parse(&settings.to_config());
+        assert!(parsed.realtime);
+        assert!(!parsed.scanlines);
+        assert_eq!(parsed.difficulty, Difficulty::Hard);
+        assert_eq!(parsed.theme, "Amber");
+        assert!(!parsed.key_repeat);
+        assert_eq!(parsed.log_size, 2);
+        assert_eq!(parsed.seed, 12345);
+    }
+
+    #[test]
+    fn malformed_values_keep_defaults() {
+        let text = "realtime = maybe\n\
+                    difficulty = Impossible\n\
+                    theme =\n\
+                    seed = -4\n\
+                    log_size = lots\n";
+        let settings = Settings::parse(text);
+        let defaults = Settings::default();
+        assert_eq!(settings.to_config(), defaults.to_config());
+    }
+
+    #[test]
+    fn unknown_and_broken_lines_are_skipped() {
+        let text = "# comment\n\
+                    \n\
+                    volume = 11\n\
+                    scanlines\n\
+                    = true\n\
+                    realtime=true\n";
+        let settings = Settings::parse(text);
+        assert!(settings.realtime);
+        assert!(settings.scanlines);
+    }
+
+    #[test]
+    fn log_size_is_clamped() {
+        assert_eq!(Settings::parse("log_size = 0").log_size, 1);
+        assert_eq!(Settings::parse("log_size = 99").log_size, MAX_LOG_SIZE);
+    }
+
+    #[test]
+    fn options_wrap_around() {
+        let mut settings = Settings::default();
+        settings.next_difficulty();
+        assert_eq!(settings.difficulty, Difficulty::Hard);
+        settings.next_difficulty();
+        assert_eq!(settings.difficulty, Difficulty::Easy);
+        settings.log_size = MAX_LOG_SIZE;
+        settings.next_log_size();
+        assert_eq!(settings.log_size, 1);
+    }
+}
@@ -0,0 +1,422 @@
+//! Spawn tables
+//! Chooses which blobs and items are placed on the map for a
+//! given depth and creates their entities
+
+use super::{
+    Blob, BlocksTile, CombatStats, Difficulty, Heal, Initiative, Item, Map, Name, Player, Position,
+    Regeneration, Renderable, TileType, Viewshed,
+};
+use rltk::{RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+/// Every kind of blob that can be spawned
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum BlobKind {
+    Grey,
+    Brute,
+    Red,
+    Blue,
+    Purple,
+    Yellow,
+}
+
+impl BlobKind {
+    pub const ALL: [BlobKind; 6] = [
+        BlobKind::Grey,
+        BlobKind::Brute,
+        BlobKind::Red,
+        BlobKind::Blue,
+        BlobKind::Purple,
+        BlobKind::Yellow,
+    ];
+
+    /// Boss blobs each carry one crystal, one of each is spawned every run
+    pub const BOSSES: [BlobKind; 4] = [
+        BlobKind::Red,
+        BlobKind::Blue,
+        BlobKind::Purple,
+        BlobKind::Yellow,
+    ];
+
+    /// Name given to the entity, also used to pick the item it drops
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlobKind::Grey => "GREY",
+            BlobKind::Brute => "BRUTE",
+            BlobKind::Red => "RED",
+            BlobKind::Blue => "BLUE",
+            BlobKind::Purple => "PURPLE",
+            BlobKind::Yellow => "YELLOW",
+        }
+    }
+
+    /// Finds the kind from a blob's name, crystals share the name of the boss that dropped them
+    pub fn from_name(name: &str) -> Option<BlobKind> {
+        BlobKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+    }
+
+    /// Crystals are named after the boss that dropped them
+    pub fn is_crystal_name(name: &str) -> bool {
+        BlobKind::from_name(name).is_some_and(|kind| kind.is_boss())
+    }
+
+    pub fn color(&self) -> RGB {
+        match self {
+            BlobKind::Grey | BlobKind::Brute => RGB::named(rltk::GREY),
+            BlobKind::Red => RGB::named(rltk::RED),
+            BlobKind::Blue => RGB::named(rltk::BLUE),
+            BlobKind::Purple => RGB::named(rltk::PURPLE),
+            BlobKind::Yellow => RGB::named(rltk::YELLOW),
+        }
+    }
+
+    pub fn glyph(&self) -> rltk::FontCharType {
+        match self {
+            BlobKind::Grey => rltk::to_cp437('o'),
+            _ => rltk::to_cp437('O'),
+        }
+    }
+
+    pub fn max_hp(&self) -> i32 {
+        match self {
+            BlobKind::Brute => 2,
+            _ => 1,
+        }
+    }
+
+    /// Grey blobs are quick, bosses are slow, the player has a speed of 25
+    pub fn speed(&self) -> i32 {
+        match self {
+            BlobKind::Grey => 30,
+            BlobKind::Brute => 25,
+            _ => 20,
+        }
+    }
+
+    pub fn is_boss(&self) -> bool {
+        BlobKind::BOSSES.contains(self)
+    }
+}
+
+/// Items that can be placed on the map when it is generated
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ItemKind {
+    Health,
+}
+
+/// A single weighted option in a random table
+#[derive(Clone, Debug)]
+pub struct RandomEntry<T> {
+    pub kind: T,
+    pub weight: i32,
+}
+
+/// Picks one of its entries with a chance proportional to its weight
+#[derive(Clone, Debug)]
+pub struct RandomTable<T> {
+    pub entries: Vec<RandomEntry<T>>,
+    pub total_weight: i32,
+}
+
+impl<T: Copy> Default for RandomTable<T> {
+    fn default() -> Self {
+        RandomTable {
+            entries: Vec::new(),
+            total_weight: 0,
+        }
+    }
+}
+
+impl<T: Copy> RandomTable<T> {
+    pub fn new() -> RandomTable<T> {
+        RandomTable::default()
+    }
+
+    /// Adds an entry, entries with no weight are never picked
+    pub fn add(mut self, kind: T, weight: i32) -> RandomTable<T> {
+        if weight > 0 {
+            self.total_weight += weight;
+            self.entries.push(RandomEntry { kind, weight });
+        }
+        self
+    }
+
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<T> {
+        if self.total_weight == 0 {
+            return None;
+        }
+        let mut roll = rng.roll_dice(1, self.total_weight) - 1;
+        for entry in self.entries.iter() {
+            if roll < entry.weight {
+                return Some(entry.kind);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
+
+/// Everything that decides what is spawned at a depth
+#[derive(Clone, Debug)]
+pub struct SpawnTable {
+    pub depth: i32,
+    pub bosses: Vec<BlobKind>,
+    pub blobs: RandomTable<BlobKind>,
+    pub blob_count: i32,
+    pub items: RandomTable<ItemKind>,
+    pub item_count: i32,
+}
+
+/// The rolled result of a spawn table
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnList {
+    pub blobs: Vec<BlobKind>,
+    pub items: Vec<ItemKind>,
+}
+
+/// Builds the spawn table for a depth, deeper levels and harder
+/// difficulties get more and tougher blobs, brutes are rare on the first level
+pub fn spawn_table(depth: i32, difficulty: Difficulty) -> SpawnTable {
+    let depth = depth.max(1);
+    let preset = difficulty.preset();
+    SpawnTable {
+        depth,
+        bosses: BlobKind::BOSSES.to_vec(),
+        blobs: RandomTable::new()
+            .add(BlobKind::Grey, 10)
+            .add(BlobKind::Brute, 2 + (depth - 1) * 3),
+        blob_count: preset.extra_blobs + depth - 1,
+        items: RandomTable::new().add(ItemKind::Health, 10),
+        item_count: (4 - depth).max(1),
+    }
+}
+
+impl SpawnTable {
+    /// Rolls the table, every boss is always included exactly once
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> SpawnList {
+        let mut blobs = self.bosses.clone();
+        for _ in 0..self.blob_count {
+            if let Some(kind) = self.blobs.roll(rng) {
+                blobs.push(kind);
+            }
+        }
+
+        let mut items = Vec::new();
+        for _ in 0..self.item_count {
+            if let Some(kind) = self.items.roll(rng) {
+                items.push(kind);
+            }
+        }
+
+        SpawnList { blobs, items }
+    }
+}
+
+/// Picks a random empty floor tile, blobs are kept outside of the rooms
+fn random_location(map: &Map, rng: &mut RandomNumberGenerator, outside_rooms: bool) -> (i32, i32) {
+    loop {
+        let x = rng.roll_dice(1, map.width - 1);
+        let y = rng.roll_dice(1, map.height - 1);
+        let idx = map.index(x, y);
+        if map.tiles[idx] != TileType::Floor || !map.tile_content[idx].is_empty() {
+            continue;
+        }
+        if outside_rooms && map.rooms.iter().any(|room| room.inside((x, y))) {
+            continue;
+        }
+        return (x, y);
+    }
+}
+
+/// Creates the player entity
+pub fn player(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let hp = ecs.fetch::<Difficulty>().preset().player_hp;
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+        })
+        .with(Player {})
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: 8,
+        })
+        .with(Name {
+            name: "Player".to_string(),
+        })
+        .with(CombatStats { max_hp: hp, hp })
+        .with(Initiative {
+            speed: 25,
+            energy: 0,
+        })
+        .with(Regeneration { turns: 0 })
+        .build()
+}
+
+/// Creates a blob entity, its health and sight depend on the difficulty
+pub fn blob(ecs: &mut World, kind: BlobKind, x: i32, y: i32) -> Entity {
+    let preset = ecs.fetch::<Difficulty>().preset();
+    let hp = kind.max_hp() + preset.blob_hp_bonus;
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: kind.glyph(),
+            fg: kind.color(),
+            bg: RGB::named(rltk::BLACK),
+        })
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: preset.blob_sight,
+        })
+        .with(Blob {})
+        .with(Name {
+            name: kind.name().to_string(),
+        })
+        .with(BlocksTile {})
+        .with(CombatStats { max_hp: hp, hp })
+        .with(Initiative {
+            speed: kind.speed(),
+            energy: 0,
+        })
+        .build()
+}
+
+/// Creates a health pickup entity, it heals less on harder difficulties
+pub fn health(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let heal_amount = ecs.fetch::<Difficulty>().preset().heal_amount;
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('+'),
+            fg: RGB::named(rltk::RED),
+            bg: RGB::named(rltk::BLACK),
+        })
+        .with(Name {
+            name: "Health".to_string(),
+        })
+        .with(Item {})
+        .with(Heal { heal_amount })
+        .build()
+}
+
+/// Rolls the spawn table for the depth and difficulty and places everything on the map
+/// Each entity is put on the map as it is spawned so nothing shares a tile
+pub fn spawn_level(ecs: &mut World, map: &mut Map, depth: i32, rng: &mut RandomNumberGenerator) {
+    let difficulty = *ecs.fetch::<Difficulty>();
+    let list = spawn_table(depth, difficulty).roll(rng);
+
+    for kind in list.blobs {
+        let (x, y) = random_location(map, rng, true);
+        let entity = blob(ecs, kind, x, y);
+        map.move_entity(entity, map.index(x, y), true);
+    }
+
+    for kind in list.items {
+        let (x, y) = random_location(map, rng, false);
+        let entity = match kind {
+            ItemKind::Health => health(ecs, x, y),
+        };
+        map.move_entity(entity, map.index(x, y), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_weights_and_counts() {
+        for difficulty in Difficulty::ALL.iter() {
+            let table = spawn_table(1, *difficulty);
+            assert_eq!(table.depth, 1);
+            assert_eq!(table.bosses, BlobKind::BOSSES.to_vec());
+            assert_eq!(table.blob_count, difficulty.preset().extra_blobs);
+            assert_eq!(table.item_count, 3);
+
+            let weights: Vec<(BlobKind, i32)> = table
+                .blobs
+                .entries
+                .iter()
+                .map(|entry| (entry.kind, entry.weight))
+                .collect();
+            assert_eq!(weights, vec![(BlobKind::Grey, 10), (BlobKind::Brute, 2)]);
+            assert_eq!(table.blobs.total_weight, 12);
+            assert_eq!(table.items.total_weight, 10);
+        }
+    }
+
+    #[test]
+    fn deeper_tables_are_tougher() {
+        let shallow = spawn_table(1, Difficulty::Normal);
+        let deep = spawn_table(3, Difficulty::Normal);
+        assert_eq!(deep.blob_count, shallow.blob_count + 2);
+        assert_eq!(deep.item_count, 1);
+        assert!(deep.blobs.entries[1].weight > shallow.blobs.entries[1].weight);
+        assert_eq!(spawn_table(0, Difficulty::Normal).depth, 1);
+    }
+
+    #[test]
+    fn every_boss_spawns_once() {
+        for difficulty in Difficulty::ALL.iter() {
+            let table = spawn_table(1, *difficulty);
+            for seed in 0..50 {
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let list = table.roll(&mut rng);
+                for boss in BlobKind::BOSSES.iter() {
+                    let count = list.blobs.iter().filter(|kind| *kind == boss).count();
+                    assert_eq!(count, 1, "{:?} with seed {}", boss, seed);
+                }
+                let extras: Vec<&BlobKind> =
+                    list.blobs.iter().filter(|kind| !kind.is_boss()).collect();
+                assert_eq!(extras.len() as i32, table.blob_count);
+                assert!(extras
+                    .iter()
+                    .all(|kind| **kind == BlobKind::Grey || **kind == BlobKind::Brute));
+                assert_eq!(list.items, vec![ItemKind::Health; 3]);
+            }
+        }
+    }
+
+    #[test]
+    fn rolls_repeat_with_the_same_seed() {
+        let table = spawn_table(1, Difficulty::Hard);
+        let first = table.roll(&mut RandomNumberGenerator::seeded(7));
+        let second = table.roll(&mut RandomNumberGenerator::seeded(7));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn brutes_spawn_on_the_first_level() {
+        let table = spawn_table(1, Difficulty::Hard);
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let brutes = (0..200)
+            .filter_map(|_| table.blobs.roll(&mut rng))
+            .filter(|kind| *kind == BlobKind::Brute)
+            .count();
+        assert!(brutes > 0);
+    }
+
+    #[test]
+    fn kinds_are_found_by_name() {
+        for kind in BlobKind::ALL.iter() {
+            assert_eq!(BlobKind::from_name(kind.name()), Some(*kind));
+        }
+        assert_eq!(BlobKind::from_name("Health"), None);
+        assert!(BlobKind::is_crystal_name("RED"));
+        assert!(!BlobKind::is_crystal_name("BRUTE"));
+        assert!(!BlobKind::is_crystal_name("Health"));
+    }
+
+    #[test]
+    fn empty_table_rolls_nothing() {
+        let table: RandomTable<BlobKind> = RandomTable::new().add(BlobKind::Grey, 0);
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert!(table.entries.is_empty());
+        assert_eq!(table.roll(&mut rng), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Swarm tactics
+//! A blackboard shared by every blob, rebuilt before the blobs act so they
+//! spread out around the player instead of queueing up behind each other
+
+use super::{Blob, BlobKind, Map, Name, Position, Viewshed};
+use rltk::Point;
+use specs::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Blobs closer than this head for their own tile next to the player
+const SURROUND_DISTANCE: f32 = 5.0;
+
+/// Bosses wait this far from the player until other blobs have engaged
+const HANG_BACK_DISTANCE: f32 = 4.0;
+
+#[derive(Default)]
+pub struct Swarm {
+    /// Free tile next to the player each hunting blob is heading for
+    pub targets: HashMap<Entity, usize>,
+    /// Bosses that should hold their ground this turn
+    pub hang_back: HashSet<Entity>,
+    /// Door tiles a blob is already going through, the rest wait outside
+    pub doors: HashMap<usize, Entity>,
+}
+
+/// The door a tile belongs to, either the door itself or the tile just inside or outside it
+pub fn doorway(map: &Map, idx: usize) -> Option<usize> {
+    if map.tiles[idx].is_door() {
+        return Some(idx);
+    }
+    let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .iter()
+        .map(|(delta_x, delta_y)| (x + delta_x, y + delta_y))
+        .filter(|(x, y)| *x >= 0 && *x < map.width && *y >= 0 && *y < map.height)
+        .map(|(x, y)| map.index(x, y))
+        .find(|idx| map.tiles[*idx].is_door())
+}
+
+impl Swarm {
+    /// Claims a door for a blob, fails if another blob already has it
+    pub fn claim_door(&mut self, idx: usize, entity: Entity) -> bool {
+        *self.doors.entry(idx).or_insert(entity) == entity
+    }
+
+    /// Lets go of any door the blob was holding
+    pub fn leave_door(&mut self, entity: Entity) {
+        self.doors.retain(|_, holder| *holder != entity);
+    }
+}
+
+pub struct SwarmSystem {}
+
+impl<'a> System<'a> for SwarmSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        Entities<'a>,
+        ReadStorage<'a, Blob>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, Swarm>,
+    );
+
+    /// Hands out the tiles around the player closest blob first,
+    /// and holds back the bosses until another blob is fighting
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, player_pos, entities, blobs, positions, viewsheds, names, mut swarm) = data;
+        swarm.targets.clear();
+        swarm.hang_back.clear();
+        swarm.doors.clear();
+
+        // Open tiles a blob could attack the player from, tiles another blob
+        // is standing on are left out so nobody is sent somewhere it can't reach
+        let mut free: Vec<usize> = Vec::new();
+        for delta_y in -1..=1 {
+            for delta_x in -1..=1 {
+                let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+                if (delta_x == 0 && delta_y == 0)
+                    || x < 0
+                    || x >= map.width
+                    || y < 0
+                    || y >= map.height
+                {
+                    continue;
+                }
+                let idx = map.index(x, y);
+                if !map.is_blocked(idx)
+                    && !map.safe[idx]
+                    && map.diagonal_allowed(player_pos.x, player_pos.y, delta_x, delta_y)
+                {
+                    free.push(idx);
+                }
+            }
+        }
+
+        let mut hunters: Vec<(Entity, Point, f32, bool)> = Vec::new();
+        let mut engaged = 0;
+        let mut minions = 0;
+        for (entity, _blob, pos, viewshed, name) in
+            (&entities, &blobs, &positions, &viewsheds, &names).join()
+        {
+            let idx = map.index(pos.x, pos.y);
+            if let Some(door) = doorway(&map, idx) {
+                swarm.doors.entry(door).or_insert(entity);
+            }
+            if !viewshed.visible_tiles.contains(&*player_pos) {
+                continue;
+            }
+            let boss = BlobKind::is_crystal_name(&name.name);
+            let point = Point::new(pos.x, pos.y);
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(point, *player_pos);
+            if !boss {
+                minions += 1;
+            }
+            // Blobs already next to the player keep their tile
+            if distance < 1.5 {
+                free.retain(|tile| *tile != idx);
+                if !boss {
+                    engaged += 1;
+                }
+            } else {
+                hunters.push((entity, point, distance, boss));
+            }
+        }
+
+        // Closest blobs pick first so nobody walks past an open tile
+        hunters.sort_by(|a, b| a.2.total_cmp(&b.2));
+        for (entity, point, distance, boss) in hunters {
+            if boss && engaged == 0 && minions > 0 && distance <= HANG_BACK_DISTANCE {
+                swarm.hang_back.insert(entity);
+                continue;
+            }
+            if distance > SURROUND_DISTANCE {
+                continue;
+            }
+            let nearest = free
+                .iter()
+                .enumerate()
+                .map(|(i, tile)| {
+                    let tile_point = Point::new(*tile as i32 % map.width, *tile as i32 / map.width);
+                    (
+                        i,
+                        rltk::DistanceAlg::Pythagoras.distance2d(point, tile_point),
+                    )
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((i, _)) = nearest {
+                swarm.targets.insert(entity, free.remove(i));
+            }
+        }
+    }
+}
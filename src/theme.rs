@@ -0,0 +1,339 @@
+//! Themes
+//! Tables of the glyph and colors used to draw each tile type,
+//! loaded from a file in the config folder so new ones can be added
+
+use super::{config, TileType};
+use rltk::{FontCharType, RGB};
+use std::fs;
+
+/// File in the config folder holding the themes
+const THEMES_FILE: &str = "themes.cfg";
+
+/// How a single tile type is drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TileStyle {
+    pub glyph: FontCharType,
+    pub fg: RGB,
+    pub bg: RGB,
+}
+
+/// A named set of tile styles, one for every tile type
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    tiles: Vec<TileStyle>,
+}
+
+/// Walls are joined to their neighbours when drawn,
+/// the wall glyph is only used for walls standing on their own
+fn default_glyph(tile: TileType) -> FontCharType {
+    rltk::to_cp437(match tile {
+        TileType::Wall => '■',
+        TileType::Floor => ' ',
+        TileType::HDoor => '─',
+        TileType::VDoor => '│',
+    })
+}
+
+impl Theme {
+    /// Builds a theme with the default glyphs, walls and doors share a color
+    fn built_in(name: &str, wall: RGB, door: RGB, floor: TileStyle) -> Theme {
+        let black = RGB::named(rltk::BLACK);
+        let tiles = TileType::ALL
+            .iter()
+            .map(|tile| match tile {
+                TileType::Floor => floor,
+                _ => TileStyle {
+                    glyph: default_glyph(*tile),
+                    fg: if tile.is_door() { door } else { wall },
+                    bg: black,
+                },
+            })
+            .collect();
+        Theme {
+            name: name.to_string(),
+            tiles,
+        }
+    }
+
+    /// The original green terminal look
+    pub fn classic() -> Theme {
+        let green = RGB::from_f32(0.0, 1.0, 0.0);
+        Theme::built_in(
+            "Classic",
+            green,
+            green,
+            TileStyle {
+                glyph: default_glyph(TileType::Floor),
+                fg: RGB::from_f32(0.5, 0.5, 0.5),
+                bg: RGB::named(rltk::BLACK),
+            },
+        )
+    }
+
+    /// Bright walls, yellow doors and dotted floors for readability
+    pub fn high_contrast() -> Theme {
+        Theme::built_in(
+            "HighContrast",
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::YELLOW),
+            TileStyle {
+                glyph: rltk::to_cp437('·'),
+                fg: RGB::from_f32(0.4, 0.4, 0.4),
+                bg: RGB::named(rltk::BLACK),
+            },
+        )
+    }
+
+    fn monochrome(name: &str, color: RGB) -> Theme {
+        let mut theme = Theme::classic();
+        theme.name = name.to_string();
+        for (tile, style) in TileType::ALL.iter().zip(theme.tiles.iter_mut()) {
+            if *tile != TileType::Floor {
+                style.fg = color;
+            }
+        }
+        theme
+    }
+
+    pub fn style(&self, tile: TileType) -> TileStyle {
+        let idx = TileType::ALL
+            .iter()
+            .position(|other| *other == tile)
+            .unwrap_or(0);
+        self.tiles[idx]
+    }
+
+    fn set_style(&mut self, tile: TileType, style: TileStyle) {
+        if let Some(idx) = TileType::ALL.iter().position(|other| *other == tile) {
+            self.tiles[idx] = style;
+        }
+    }
+}
+
+/// Every theme that can be picked on the options screen
+pub struct Themes {
+    pub themes: Vec<Theme>,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Themes {
+            themes: vec![
+                Theme::classic(),
+                Theme::high_contrast(),
+                Theme::monochrome("Amber", RGB::from_f32(1.0, 0.7, 0.0)),
+                Theme::monochrome("Blue", RGB::from_f32(0.3, 0.6, 1.0)),
+            ],
+        }
+    }
+}
+
+/// Reads a single character glyph or a code page 437 number
+fn parse_glyph(value: &str) -> Option<FontCharType> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(rltk::to_cp437(c)),
+        _ => value.parse().ok(),
+    }
+}
+
+/// Spaces would be lost when the line is trimmed so they are written as numbers
+fn glyph_to_config(glyph: FontCharType) -> String {
+    let c = rltk::to_char(glyph as u8);
+    if glyph > 255 || c.is_whitespace() {
+        glyph.to_string()
+    } else {
+        c.to_string()
+    }
+}
+
+fn color_to_config(color: RGB) -> String {
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    format!(
+        "#{:02X}{:02X}{:02X}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+/// Reads `glyph, foreground, background`
+fn parse_style(value: &str) -> Option<TileStyle> {
+    let parts: Vec<&str> = value.split(',').map(|part| part.trim()).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    Some(TileStyle {
+        glyph: parse_glyph(parts[0])?,
+        fg: RGB::from_hex(parts[1]).ok()?,
+        bg: RGB::from_hex(parts[2]).ok()?,
+    })
+}
+
+impl Themes {
+    /// Finds a theme by name, falling back to the first one
+    pub fn get(&self, name: &str) -> &Theme {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or(&self.themes[0])
+    }
+
+    /// Name of the theme after the given one, wrapping back to the start
+    pub fn next(&self, name: &str) -> String {
+        let idx = self
+            .themes
+            .iter()
+            .position(|theme| theme.name == name)
+            .unwrap_or(0);
+        self.themes[(idx + 1) % self.themes.len()].name.clone()
+    }
+
+    /// Reads `[Name]` sections of `Tile = glyph, foreground, background` lines
+    /// Tiles a theme leaves out keep the classic style
+    pub fn parse(text: &str) -> Themes {
+        let mut themes: Vec<Theme> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let mut theme = Theme::classic();
+                theme.name = line[1..line.len() - 1].trim().to_string();
+                themes.push(theme);
+                continue;
+            }
+            let theme = match themes.last_mut() {
+                Some(theme) => theme,
+                None => continue,
+            };
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            let tile = TileType::ALL
+                .iter()
+                .find(|tile| format!("{:?}", tile) == name);
+            if let (Some(tile), Some(style)) = (tile, parse_style(value)) {
+                theme.set_style(*tile, style);
+            }
+        }
+
+        if themes.is_empty() {
+            Themes::default()
+        } else {
+            Themes { themes }
+        }
+    }
+
+    /// Writes the themes in the same format `parse` reads
+    pub fn to_config(&self) -> String {
+        let mut config = String::from(
+            "# Attack of the Blobs themes\n\
+             # Each [Name] starts a theme, tiles are drawn as `Tile = glyph, foreground, background`\n\
+             # The glyph is a single character or a code page 437 number\n\
+             # Walls next to other walls or doors are joined with box drawing glyphs\n",
+        );
+        for theme in self.themes.iter() {
+            config.push_str(&format!("\n[{}]\n", theme.name));
+            for tile in TileType::ALL.iter() {
+                let style = theme.style(*tile);
+                config.push_str(&format!(
+                    "{:?} = {}, {}, {}\n",
+                    tile,
+                    glyph_to_config(style.glyph),
+                    color_to_config(style.fg),
+                    color_to_config(style.bg)
+                ));
+            }
+        }
+        config
+    }
+
+    /// Loads the themes from the config folder, writing the built in themes if there are none
+    pub fn load() -> Themes {
+        let path = config::config_file(THEMES_FILE);
+        match fs::read_to_string(&path) {
+            Ok(text) => Themes::parse(&text),
+            Err(_) => {
+                let themes = Themes::default();
+                let _ = fs::write(&path, themes.to_config());
+                themes
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Colors are saved with 8 bits a channel, so compare the written files
+    #[test]
+    fn built_in_themes_round_trip() {
+        let themes = Themes::default();
+        let config = themes.to_config();
+        let parsed = Themes::parse(&config);
+        assert_eq!(parsed.to_config(), config);
+        for (parsed, theme) in parsed.themes.iter().zip(themes.themes.iter()) {
+            assert_eq!(parsed.name, theme.name);
+            for tile in TileType::ALL.iter() {
+                assert_eq!(parsed.style(*tile).glyph, theme.style(*tile).glyph);
+            }
+        }
+    }
+
+    #[test]
+    fn custom_theme_keeps_classic_for_missing_tiles() {
+        let themes = Themes::parse("[Night]\nWall = #, #102030, #000000\n");
+        let night = themes.get("Night");
+        assert_eq!(night.name, "Night");
+        assert_eq!(
+            night.style(TileType::Wall),
+            TileStyle {
+                glyph: rltk::to_cp437('#'),
+                fg: RGB::from_u8(0x10, 0x20, 0x30),
+                bg: RGB::from_u8(0, 0, 0),
+            }
+        );
+        assert_eq!(
+            night.style(TileType::Floor),
+            Theme::classic().style(TileType::Floor)
+        );
+    }
+
+    #[test]
+    fn numeric_glyphs_and_spaces() {
+        let themes = Themes::parse("[Dots]\nFloor = 250, #808080, #000000\n");
+        assert_eq!(themes.get("Dots").style(TileType::Floor).glyph, 250);
+        assert_eq!(glyph_to_config(rltk::to_cp437(' ')), "32");
+        assert_eq!(parse_glyph("32"), Some(rltk::to_cp437(' ')));
+    }
+
+    #[test]
+    fn malformed_and_unknown_lines_are_skipped() {
+        let text = "Wall = #, #FFFFFF, #000000\n\
+                    # comment\n\
+                    [Broken]\n\
+                    Lava = ~, #FF0000, #000000\n\
+                    Wall = #, #FFFFFF\n\
+                    Wall = ##, #FFFFFF, #000000\n\
+                    Wall = #, white, #000000\n\
+                    not a tile\n";
+        let themes = Themes::parse(text);
+        assert_eq!(themes.themes.len(), 1);
+        let broken = themes.get("Broken");
+        for tile in TileType::ALL.iter() {
+            assert_eq!(broken.style(*tile), Theme::classic().style(*tile));
+        }
+    }
+
+    #[test]
+    fn empty_file_uses_built_in_themes() {
+        let themes = Themes::parse("# nothing here\n");
+        assert_eq!(themes.themes.len(), Themes::default().themes.len());
+        assert_eq!(themes.get("Missing").name, "Classic");
+        assert_eq!(themes.next("Blue"), "Classic");
+    }
+}
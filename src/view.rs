@@ -1,6 +1,6 @@
-///! Vision system that allows the blobs to see the player
+//! Vision system that allows the blobs to see the player
 
-use super::{Map, Position, Viewshed};
+use super::{Map, Player, Position, Viewshed};
 use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
@@ -12,14 +12,24 @@ impl<'a> System<'a> for VisibilitySystem {
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
     );
     /// Scans the map for all entities in range of other entities
+    /// and reveals the tiles the player can see
     fn run(&mut self, data: Self::SystemData) {
-        let (map, entities, mut viewshed, pos) = data;
+        let (mut map, entities, mut viewshed, pos, player) = data;
 
-        for (_ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
+        for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             // Updates all tiles in view 
             viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+
+            // Remember everything the player has seen
+            if player.get(ent).is_some() {
+                for tile in viewshed.visible_tiles.iter() {
+                    let idx = map.index(tile.x, tile.y);
+                    map.revealed_tiles[idx] = true;
+                }
+            }
         }
     }
 }